@@ -1,5 +1,29 @@
+use serde::Deserialize;
 use std::{fmt::Debug, rc::Rc};
 
+/// Stack left when recursing over a tree before switching to a new stack
+/// segment, so that deeply nested programs do not overflow it.
+pub(crate) const RED_ZONE: usize = 64 * 1024;
+/// Size of the stack segments allocated when running out of stack.
+pub(crate) const STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// (De)serialization of the children of a term, on a new stack segment when
+/// needed.
+mod grow {
+    use super::{RED_ZONE, STACK_SIZE};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T: Serialize, S: Serializer>(value: &T, ser: S) -> Result<S::Ok, S::Error> {
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || value.serialize(ser))
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        dsz: D,
+    ) -> Result<T, D::Error> {
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || T::deserialize(dsz))
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Var {
     pub text: String,
@@ -25,6 +49,38 @@ pub struct File {
     pub location: Location,
 }
 
+impl File {
+    /// Read a JSON AST, in the format of the official parser.
+    pub fn from_json(json: &str) -> serde_json::Result<File> {
+        let mut dsz = serde_json::Deserializer::from_str(json);
+        dsz.disable_recursion_limit();
+        let mut dsz = serde_stacker::Deserializer::new(&mut dsz);
+        dsz.red_zone = RED_ZONE;
+        dsz.stack_size = STACK_SIZE;
+        File::deserialize(dsz)
+    }
+
+    /// Write the JSON AST in the format of the official parser, indented
+    /// when `pretty`.
+    /// ```rust
+    /// use rinha_compiladores::ast::File;
+    ///
+    /// let file = File::from(rinha::parser::parse_or_report("a.rinha", "x").expect("parse error"));
+    /// let location = r#"{"start":0,"end":1,"filename":"a.rinha"}"#;
+    ///
+    /// assert_eq!(
+    ///     file.to_json(false).unwrap(),
+    ///     format!(r#"{{"name":"a.rinha","expression":{{"kind":"Var","text":"x","location":{location}}},"location":{location}}}"#),
+    /// );
+    /// ```
+    pub fn to_json(&self, pretty: bool) -> serde_json::Result<String> {
+        match pretty {
+            true => serde_json::to_string_pretty(self),
+            false => serde_json::to_string(self),
+        }
+    }
+}
+
 impl From<rinha::ast::File> for File {
     fn from(value: rinha::ast::File) -> Self {
         Self {
@@ -123,8 +179,11 @@ impl Element for Error {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct If {
+    #[serde(with = "grow")]
     pub condition: Box<Term>,
+    #[serde(with = "grow")]
     pub then: Box<Term>,
+    #[serde(with = "grow")]
     pub otherwise: Box<Term>,
     pub location: Location,
 }
@@ -143,11 +202,60 @@ impl From<rinha::ast::If> for If {
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Let {
     pub name: Var,
+    #[serde(with = "grow")]
     pub value: Box<Term>,
+    #[serde(with = "grow")]
     pub next: Box<Term>,
     pub location: Location,
 }
 
+impl Let {
+    /// The `let`s defining functions that can all call each other: this one
+    /// and the ones right after it that define a function an earlier one of
+    /// the group refers to. Empty when this one does not define a function.
+    ///
+    /// A `let` redefining a name of the group or a name `bound` in the
+    /// enclosing scopes ends the group, so that no function sees a later
+    /// binding in place of one that was visible where it was defined.
+    /// ```rust
+    /// use rinha_compiladores::ast::Term;
+    ///
+    /// let source = "let even = fn (n) => { (n == 0) || odd(n - 1) };
+    ///     let odd = fn (n) => { (n != 0) && even(n - 1) };
+    ///     let zero = 0;
+    ///     even(10)";
+    /// let file = rinha::parser::parse_or_report("example", source).expect("parse error");
+    /// let term = Term::from(file.expression);
+    /// let Term::Let(l) = &term else { panic!("not a let") };
+    ///
+    /// let names: Vec<_> = l.group(|_| false).iter().map(|l| l.name.text.as_str()).collect();
+    /// assert_eq!(names, ["even", "odd"]);
+    /// ```
+    pub fn group(&self, bound: impl Fn(&str) -> bool) -> Vec<&Let> {
+        let mut group: Vec<&Let> = Vec::new();
+        let mut term = Some(self);
+        while let Some(l) = term {
+            let name = &l.name.text;
+            if !matches!(l.value.as_ref(), Term::Function(_)) {
+                break;
+            }
+            if !group.is_empty() {
+                let redefined = group.iter().any(|g| &g.name.text == name);
+                let referred = group.iter().any(|g| g.value.refers_to(name));
+                if redefined || bound(name) || !referred {
+                    break;
+                }
+            }
+            group.push(l);
+            term = match l.next.as_ref() {
+                Term::Let(next) => Some(next),
+                _ => None,
+            };
+        }
+        group
+    }
+}
+
 impl From<rinha::ast::Let> for Let {
     fn from(value: rinha::ast::Let) -> Self {
         Self {
@@ -232,7 +340,7 @@ impl From<rinha::ast::Int> for Int {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum BinaryOp {
     Add, // Add
     Sub, // Subtract
@@ -271,8 +379,10 @@ impl From<rinha::ast::BinaryOp> for BinaryOp {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Binary {
+    #[serde(with = "grow")]
     pub lhs: Box<Term>,
     pub op: BinaryOp,
+    #[serde(with = "grow")]
     pub rhs: Box<Term>,
     pub location: Location,
 }
@@ -296,7 +406,9 @@ impl From<rinha::ast::Binary> for Binary {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Call {
+    #[serde(with = "grow")]
     pub callee: Box<Term>,
+    #[serde(with = "grow")]
     pub arguments: Vec<Term>,
     pub location: Location,
 }
@@ -320,6 +432,7 @@ impl Element for Call {
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Function {
     pub parameters: Vec<Var>,
+    #[serde(with = "grow")]
     pub value: Box<Term>,
     pub location: Location,
 }
@@ -349,6 +462,7 @@ impl Element for Function {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Print {
+    #[serde(with = "grow")]
     pub value: Box<Term>,
     pub location: Location,
 }
@@ -370,6 +484,7 @@ impl Element for Print {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct First {
+    #[serde(with = "grow")]
     pub value: Box<Term>,
     pub location: Location,
 }
@@ -391,6 +506,7 @@ impl Element for First {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Second {
+    #[serde(with = "grow")]
     pub value: Box<Term>,
     pub location: Location,
 }
@@ -412,7 +528,9 @@ impl Element for Second {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Tuple {
+    #[serde(with = "grow")]
     pub first: Box<Term>,
+    #[serde(with = "grow")]
     pub second: Box<Term>,
     pub location: Location,
 }
@@ -457,7 +575,8 @@ pub enum Term {
 
 impl From<rinha::ast::Term> for Term {
     fn from(value: rinha::ast::Term) -> Self {
-        match value {
+        // The conversion recurses as deep as the program is nested.
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || match value {
             rinha::ast::Term::Error(e) => Self::Error(e.into()),
             rinha::ast::Term::Int(i) => Self::Int(i.into()),
             rinha::ast::Term::Str(s) => Self::Str(s.into()),
@@ -472,7 +591,73 @@ impl From<rinha::ast::Term> for Term {
             rinha::ast::Term::Bool(b) => Self::Bool(b.into()),
             rinha::ast::Term::Tuple(t) => Self::Tuple(t.into()),
             rinha::ast::Term::Var(v) => Self::Var(v.into()),
-        }
+        })
+    }
+}
+
+impl Drop for Term {
+    fn drop(&mut self) {
+        // Dropping recurses as deep as the term is nested, so the children
+        // are taken out and dropped on a stack grown as needed.
+        let take = |term: &mut Term| drop(std::mem::replace(term, Term::Int(Int::default())));
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || match self {
+            Term::Call(c) => {
+                take(&mut c.callee);
+                c.arguments.iter_mut().for_each(take);
+            }
+            Term::Binary(b) => {
+                take(&mut b.lhs);
+                take(&mut b.rhs);
+            }
+            Term::Function(f) => take(&mut f.value),
+            Term::Let(l) => {
+                take(&mut l.value);
+                take(&mut l.next);
+            }
+            Term::If(i) => {
+                take(&mut i.condition);
+                take(&mut i.then);
+                take(&mut i.otherwise);
+            }
+            Term::Print(p) => take(&mut p.value),
+            Term::First(f) => take(&mut f.value),
+            Term::Second(s) => take(&mut s.value),
+            Term::Tuple(t) => {
+                take(&mut t.first);
+                take(&mut t.second);
+            }
+            Term::Error(_) | Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) => {}
+        })
+    }
+}
+
+impl Term {
+    /// Whether `name` is used in the term where it is not bound by the term
+    /// itself.
+    pub fn refers_to(&self, name: &str) -> bool {
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || match self {
+            Term::Var(v) => v.text == name,
+            Term::Let(l) => {
+                // A function sees the name it is bound to.
+                let own = l.name.text == name && matches!(l.value.as_ref(), Term::Function(_));
+                (!own && l.value.refers_to(name)) || (l.name.text != name && l.next.refers_to(name))
+            }
+            Term::Function(f) => {
+                !f.parameters.iter().any(|p| p.text == name) && f.value.refers_to(name)
+            }
+            Term::If(i) => {
+                i.condition.refers_to(name) || i.then.refers_to(name) || i.otherwise.refers_to(name)
+            }
+            Term::Binary(b) => b.lhs.refers_to(name) || b.rhs.refers_to(name),
+            Term::Call(c) => {
+                c.callee.refers_to(name) || c.arguments.iter().any(|arg| arg.refers_to(name))
+            }
+            Term::Print(p) => p.value.refers_to(name),
+            Term::First(f) => f.value.refers_to(name),
+            Term::Second(s) => s.value.refers_to(name),
+            Term::Tuple(t) => t.first.refers_to(name) || t.second.refers_to(name),
+            Term::Error(_) | Term::Int(_) | Term::Str(_) | Term::Bool(_) => false,
+        })
     }
 }
 
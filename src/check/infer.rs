@@ -0,0 +1,557 @@
+use std::mem::take;
+
+use super::types::Type;
+use super::TypeError;
+use crate::ast::{BinaryOp, Element, Function, Let, Location, Term, RED_ZONE, STACK_SIZE};
+
+/// Type of a `let` binding, generalized over `vars`.
+#[derive(Debug, Clone)]
+struct Scheme {
+    vars: Vec<u32>,
+    ty: Type,
+}
+
+impl Scheme {
+    /// Scheme of a binding that is not generalized, like a parameter.
+    fn mono(ty: Type) -> Self {
+        Self {
+            vars: Vec::new(),
+            ty,
+        }
+    }
+}
+
+/// A type variable: what it was unified with, if anything, and the `let`
+/// nesting level it belongs to. Variables deeper than the current level
+/// are generalized when a `let` is done.
+struct Var {
+    binding: Option<Type>,
+    level: usize,
+}
+
+/// Typing rule of an overloaded operation, applied once its operands are
+/// known enough to choose an overload.
+enum Deferred {
+    /// `lhs + rhs` of type `result`: adds integers, concatenates when either
+    /// side is a string.
+    Add {
+        lhs: Type,
+        rhs: Type,
+        result: Type,
+        location: Location,
+    },
+    /// `first(value)`, or `second(value)`, of type `result`: an element of a
+    /// tuple or a character of a string.
+    Project {
+        second: bool,
+        value: Type,
+        result: Type,
+        location: Location,
+    },
+}
+
+enum Unify {
+    Mismatch,
+    Infinite(u32, Type),
+}
+
+/// Hindley–Milner inference with let-polymorphism, using levels to decide
+/// which variables a `let` generalizes.
+#[derive(Default)]
+pub struct Infer {
+    vars: Vec<Var>,
+    level: usize,
+    /// Bindings in scope, innermost last.
+    scope: Vec<(String, Scheme)>,
+    deferred: Vec<Deferred>,
+    pub errors: Vec<TypeError>,
+}
+
+impl Infer {
+    fn bind(&mut self, name: &str, scheme: Scheme) {
+        self.scope.push((name.to_string(), scheme));
+    }
+
+    /// Infer the type of a whole program, settling every overloaded
+    /// operation left.
+    pub fn program(&mut self, term: &Term) -> Type {
+        let ty = self.term(term);
+        self.solve(true);
+        self.resolve(&ty)
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.vars.push(Var {
+            binding: None,
+            level: self.level,
+        });
+        Type::Var((self.vars.len() - 1) as u32)
+    }
+
+    /// Follow the bindings of a variable until a type that is not a bound
+    /// variable.
+    fn shallow(&self, ty: &Type) -> Type {
+        let mut ty = ty.clone();
+        while let Type::Var(var) = ty {
+            match &self.vars[var as usize].binding {
+                Some(binding) => ty = binding.clone(),
+                None => break,
+            }
+        }
+        ty
+    }
+
+    /// Replace every bound variable of a type by its binding.
+    pub fn resolve(&self, ty: &Type) -> Type {
+        match self.shallow(ty) {
+            Type::Tuple(first, second) => Type::tuple(self.resolve(&first), self.resolve(&second)),
+            Type::Fn(parameters, result) => Type::function(
+                parameters.iter().map(|p| self.resolve(p)).collect(),
+                self.resolve(&result),
+            ),
+            ty => ty,
+        }
+    }
+
+    /// Free variables of a type, in order of appearance.
+    fn free(&self, ty: &Type, vars: &mut Vec<u32>) {
+        match self.shallow(ty) {
+            Type::Var(var) if !vars.contains(&var) => vars.push(var),
+            Type::Tuple(first, second) => {
+                self.free(&first, vars);
+                self.free(&second, vars);
+            }
+            Type::Fn(parameters, result) => {
+                for parameter in &parameters {
+                    self.free(parameter, vars);
+                }
+                self.free(&result, vars);
+            }
+            _ => {}
+        }
+    }
+
+    fn generalize(&self, ty: &Type) -> Scheme {
+        let mut vars = Vec::new();
+        self.free(ty, &mut vars);
+        vars.retain(|var| self.vars[*var as usize].level > self.level);
+        Scheme {
+            vars,
+            ty: self.resolve(ty),
+        }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let fresh: Vec<_> = scheme.vars.iter().map(|_| self.fresh()).collect();
+        substitute(&scheme.ty, &scheme.vars, &fresh)
+    }
+
+    /// Unify the type a term is `expected` to have with the one it
+    /// `actual`ly has, reporting a failure at `location`.
+    fn unify(&mut self, expected: &Type, actual: &Type, location: &Location) {
+        let error = match self.unify_types(expected, actual) {
+            Ok(()) => return,
+            Err(Unify::Mismatch) => {
+                let types = Type::normalize_all(&[&self.resolve(expected), &self.resolve(actual)]);
+                TypeError::Mismatch {
+                    expected: types[0].clone(),
+                    actual: types[1].clone(),
+                    location: location.clone(),
+                }
+            }
+            Err(Unify::Infinite(var, ty)) => {
+                let types = Type::normalize_all(&[&Type::Var(var), &self.resolve(&ty)]);
+                TypeError::Infinite {
+                    var: types[0].clone(),
+                    ty: types[1].clone(),
+                    location: location.clone(),
+                }
+            }
+        };
+        self.errors.push(error);
+    }
+
+    fn unify_types(&mut self, a: &Type, b: &Type) -> Result<(), Unify> {
+        match (self.shallow(a), self.shallow(b)) {
+            (Type::Var(a), Type::Var(b)) if a == b => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => self.bind_var(var, ty),
+            (Type::Int, Type::Int) | (Type::Str, Type::Str) | (Type::Bool, Type::Bool) => Ok(()),
+            (Type::Tuple(a1, a2), Type::Tuple(b1, b2)) => {
+                self.unify_types(&a1, &b1)?;
+                self.unify_types(&a2, &b2)
+            }
+            (Type::Fn(ps, r), Type::Fn(qs, s)) if ps.len() == qs.len() => {
+                for (p, q) in ps.iter().zip(&qs) {
+                    self.unify_types(p, q)?;
+                }
+                self.unify_types(&r, &s)
+            }
+            _ => Err(Unify::Mismatch),
+        }
+    }
+
+    fn bind_var(&mut self, var: u32, ty: Type) -> Result<(), Unify> {
+        let mut vars = Vec::new();
+        self.free(&ty, &mut vars);
+        if vars.contains(&var) {
+            return Err(Unify::Infinite(var, ty));
+        }
+        // The variables of `ty` now escape as far as `var` does.
+        let level = self.vars[var as usize].level;
+        for free in vars {
+            let free = &mut self.vars[free as usize];
+            free.level = free.level.min(level);
+        }
+        self.vars[var as usize].binding = Some(ty);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Scheme> {
+        self.scope
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, s)| s)
+    }
+
+    fn function(&mut self, f: &Function) -> Type {
+        let parameters: Vec<_> = f.parameters.iter().map(|_| self.fresh()).collect();
+        for (parameter, ty) in f.parameters.iter().zip(&parameters) {
+            self.bind(&parameter.text, Scheme::mono(ty.clone()));
+        }
+        let result = self.term(&f.value);
+        self.scope.truncate(self.scope.len() - parameters.len());
+        Type::function(parameters, result)
+    }
+
+    /// Infer `let`s defining functions that can all call each other, see
+    /// [`Let::group`]. The functions see each other, without
+    /// generalization, and are generalized together.
+    fn group(&mut self, group: &[&Let]) -> Type {
+        self.level += 1;
+        let types: Vec<_> = group
+            .iter()
+            .map(|l| {
+                let ty = self.fresh();
+                self.bind(&l.name.text, Scheme::mono(ty.clone()));
+                ty
+            })
+            .collect();
+        for (l, ty) in group.iter().zip(&types) {
+            let Term::Function(f) = l.value.as_ref() else {
+                unreachable!("not a function");
+            };
+            let actual = self.function(f);
+            self.unify(ty, &actual, &f.location);
+        }
+        for _ in group {
+            self.scope.pop();
+        }
+        self.level -= 1;
+        self.solve(false);
+        for (l, ty) in group.iter().zip(&types) {
+            let scheme = self.generalize(ty);
+            self.bind(&l.name.text, scheme);
+        }
+        let last = group.last().expect("empty group");
+        let ty = self.term(&last.next);
+        for _ in group {
+            self.scope.pop();
+        }
+        ty
+    }
+
+    fn term(&mut self, term: &Term) -> Type {
+        // Inferring recurses as deep as the program is nested.
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || match term {
+            Term::Int(_) => Type::Int,
+            Term::Str(_) => Type::Str,
+            Term::Bool(_) => Type::Bool,
+            Term::Var(v) => match self.lookup(&v.text).cloned() {
+                Some(scheme) => self.instantiate(&scheme),
+                None => {
+                    self.errors.push(TypeError::Unbound {
+                        name: v.text.clone(),
+                        location: v.location.clone(),
+                    });
+                    self.fresh()
+                }
+            },
+            Term::Let(l) => {
+                let group = l.group(|name| self.lookup(name).is_some());
+                if !group.is_empty() {
+                    return self.group(&group);
+                }
+                self.level += 1;
+                let ty = self.term(&l.value);
+                self.level -= 1;
+                self.solve(false);
+                let scheme = self.generalize(&ty);
+                self.bind(&l.name.text, scheme);
+                let ty = self.term(&l.next);
+                self.scope.pop();
+                ty
+            }
+            Term::If(i) => {
+                let condition = self.term(&i.condition);
+                self.unify(&Type::Bool, &condition, i.condition.location());
+                let then = self.term(&i.then);
+                let otherwise = self.term(&i.otherwise);
+                self.unify(&then, &otherwise, i.otherwise.location());
+                then
+            }
+            Term::Binary(bin) => {
+                let lhs = self.term(&bin.lhs);
+                let rhs = self.term(&bin.rhs);
+                match bin.op {
+                    BinaryOp::Add => {
+                        let result = self.fresh();
+                        self.defer(Deferred::Add {
+                            lhs,
+                            rhs,
+                            result: result.clone(),
+                            location: bin.location.clone(),
+                        });
+                        result
+                    }
+                    BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                        self.unify(&Type::Int, &lhs, bin.lhs.location());
+                        self.unify(&Type::Int, &rhs, bin.rhs.location());
+                        Type::Int
+                    }
+                    BinaryOp::And | BinaryOp::Or => {
+                        self.unify(&Type::Bool, &lhs, bin.lhs.location());
+                        self.unify(&Type::Bool, &rhs, bin.rhs.location());
+                        Type::Bool
+                    }
+                    BinaryOp::Eq
+                    | BinaryOp::Neq
+                    | BinaryOp::Lt
+                    | BinaryOp::Gt
+                    | BinaryOp::Lte
+                    | BinaryOp::Gte => {
+                        self.unify(&lhs, &rhs, bin.rhs.location());
+                        Type::Bool
+                    }
+                }
+            }
+            Term::Call(call) => {
+                let callee = self.term(&call.callee);
+                let arguments: Vec<_> = call.arguments.iter().map(|arg| self.term(arg)).collect();
+                match self.shallow(&callee) {
+                    // Check each argument on its own for precise locations.
+                    Type::Fn(parameters, result) if parameters.len() == arguments.len() => {
+                        for ((parameter, argument), term) in
+                            parameters.iter().zip(&arguments).zip(&call.arguments)
+                        {
+                            self.unify(parameter, argument, term.location());
+                        }
+                        *result
+                    }
+                    _ => {
+                        let result = self.fresh();
+                        let expected = Type::function(arguments, result.clone());
+                        self.unify(&expected, &callee, call.callee.location());
+                        result
+                    }
+                }
+            }
+            Term::Function(f) => self.function(f),
+            Term::Print(print) => self.term(&print.value),
+            Term::First(f) => {
+                let value = self.term(&f.value);
+                let result = self.fresh();
+                self.defer(Deferred::Project {
+                    second: false,
+                    value,
+                    result: result.clone(),
+                    location: f.location.clone(),
+                });
+                result
+            }
+            Term::Second(s) => {
+                let value = self.term(&s.value);
+                let result = self.fresh();
+                self.defer(Deferred::Project {
+                    second: true,
+                    value,
+                    result: result.clone(),
+                    location: s.location.clone(),
+                });
+                result
+            }
+            Term::Tuple(t) => {
+                let first = self.term(&t.first);
+                let second = self.term(&t.second);
+                Type::tuple(first, second)
+            }
+            // Evaluating it aborts the program, so it fits anywhere.
+            Term::Error(_) => self.fresh(),
+        })
+    }
+
+    fn defer(&mut self, deferred: Deferred) {
+        if !self.step(&deferred) {
+            self.deferred.push(deferred);
+        }
+    }
+
+    /// Apply an overloaded operation if its overload can be chosen,
+    /// returning whether it was.
+    fn step(&mut self, deferred: &Deferred) -> bool {
+        match deferred {
+            Deferred::Add {
+                lhs,
+                rhs,
+                result,
+                location,
+            } => match (self.shallow(lhs), self.shallow(rhs), self.shallow(result)) {
+                (Type::Int, Type::Int, _) => self.unify(&Type::Int, result, location),
+                (Type::Str, _, _) | (_, Type::Str, _) => self.unify(&Type::Str, result, location),
+                (_, _, Type::Int) => {
+                    self.unify(&Type::Int, lhs, location);
+                    self.unify(&Type::Int, rhs, location);
+                }
+                (Type::Var(_), _, _) | (_, Type::Var(_), _) => return false,
+                (lhs, rhs, _) => {
+                    let types = Type::normalize_all(&[&self.resolve(&lhs), &self.resolve(&rhs)]);
+                    self.errors.push(TypeError::Add {
+                        lhs: types[0].clone(),
+                        rhs: types[1].clone(),
+                        location: location.clone(),
+                    });
+                }
+            },
+            Deferred::Project {
+                second,
+                value,
+                result,
+                location,
+            } => match self.shallow(value) {
+                Type::Str => self.unify(&Type::Str, result, location),
+                Type::Tuple(first, other) => {
+                    let element = if *second { other } else { first };
+                    self.unify(&element, result, location);
+                }
+                Type::Var(_) => match self.shallow(result) {
+                    // Only a tuple can have elements that are not strings.
+                    Type::Str | Type::Var(_) => return false,
+                    _ => {
+                        let tuple = Type::tuple(self.fresh(), self.fresh());
+                        self.unify(&tuple, value, location);
+                        return self.step(deferred);
+                    }
+                },
+                _ => {
+                    let tuple = Type::tuple(self.fresh(), self.fresh());
+                    self.unify(&tuple, value, location);
+                }
+            },
+        }
+        true
+    }
+
+    /// Choose the most likely overload of an operation whose operands stay
+    /// unknown: integer addition, unless a side cannot be an integer, and
+    /// tuple projections.
+    fn default(&mut self, deferred: &Deferred) {
+        match deferred {
+            Deferred::Add {
+                lhs,
+                rhs,
+                result,
+                location,
+            } => {
+                let concrete = |ty: Type| !matches!(ty, Type::Int | Type::Var(_));
+                let string = self.shallow(result) == Type::Str
+                    || concrete(self.shallow(lhs))
+                    || concrete(self.shallow(rhs));
+                match (string, self.shallow(lhs)) {
+                    (true, Type::Var(_)) => self.unify(&Type::Str, lhs, location),
+                    (true, _) => self.unify(&Type::Str, rhs, location),
+                    (false, _) => {
+                        self.unify(&Type::Int, lhs, location);
+                        self.unify(&Type::Int, rhs, location);
+                    }
+                }
+            }
+            Deferred::Project {
+                value, location, ..
+            } => {
+                let tuple = Type::tuple(self.fresh(), self.fresh());
+                self.unify(&tuple, value, location);
+            }
+        }
+    }
+
+    /// Whether an operation involves variables the current `let` would
+    /// generalize, in which case its overload must be chosen now.
+    fn generalizable(&self, deferred: &Deferred) -> bool {
+        let mut vars = Vec::new();
+        match deferred {
+            Deferred::Add {
+                lhs, rhs, result, ..
+            } => {
+                self.free(lhs, &mut vars);
+                self.free(rhs, &mut vars);
+                self.free(result, &mut vars);
+            }
+            Deferred::Project { value, result, .. } => {
+                self.free(value, &mut vars);
+                self.free(result, &mut vars);
+            }
+        }
+        vars.iter()
+            .any(|var| self.vars[*var as usize].level > self.level)
+    }
+
+    /// Apply the deferred operations whose overload is known, defaulting
+    /// the ones that would be generalized, or all of them when `all` is set.
+    fn solve(&mut self, all: bool) {
+        loop {
+            let mut progress = true;
+            while progress {
+                progress = false;
+                for deferred in take(&mut self.deferred) {
+                    match self.step(&deferred) {
+                        true => progress = true,
+                        false => self.deferred.push(deferred),
+                    }
+                }
+            }
+            let index = self
+                .deferred
+                .iter()
+                .position(|deferred| all || self.generalizable(deferred));
+            match index {
+                Some(index) => {
+                    let deferred = self.deferred.remove(index);
+                    self.default(&deferred);
+                    self.step(&deferred);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+fn substitute(ty: &Type, vars: &[u32], types: &[Type]) -> Type {
+    match ty {
+        Type::Var(var) => match vars.iter().position(|v| v == var) {
+            Some(index) => types[index].clone(),
+            None => ty.clone(),
+        },
+        Type::Tuple(first, second) => Type::tuple(
+            substitute(first, vars, types),
+            substitute(second, vars, types),
+        ),
+        Type::Fn(parameters, result) => Type::function(
+            parameters
+                .iter()
+                .map(|p| substitute(p, vars, types))
+                .collect(),
+            substitute(result, vars, types),
+        ),
+        Type::Int | Type::Str | Type::Bool => ty.clone(),
+    }
+}
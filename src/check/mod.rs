@@ -0,0 +1,105 @@
+//! Static type checking of rinha programs.
+//!
+//! Types are inferred Hindley–Milner style: functions and the `let`s bound
+//! to them are polymorphic, and no annotation is needed. Two operations are
+//! overloaded like at runtime:
+//!
+//! - `a + b` adds integers, or concatenates when either side is a string,
+//!   in which case the other side can have any type. When the operands stay
+//!   unknown, as in `fn (a, b) => { a + b }`, integer addition is assumed;
+//! - `first` and `second` take elements of tuples or characters of strings,
+//!   tuples being assumed when the argument stays unknown.
+//!
+//! Unlike the runtime, calls must pass exactly as many arguments as the
+//! function has parameters, and both branches of an `if` must have the same
+//! type. Values whose type would contain itself are rejected as well, which
+//! rules out lists encoded as nested tuples, like the ones of
+//! `examples/source.rinha`: `cons(value, rest)` would need a type
+//! `list = ((int, 'a), list)`, and the checker has no recursive types.
+
+mod infer;
+pub mod types;
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use self::infer::Infer;
+use self::types::Type;
+use crate::ast::{Location, Term};
+
+/// Error found while checking a program.
+#[derive(Debug, Clone, Error, Diagnostic)]
+pub enum TypeError {
+    #[error("type mismatch: expected {expected}, found {actual}")]
+    #[diagnostic(code(rinha::check::mismatch))]
+    Mismatch {
+        expected: Type,
+        actual: Type,
+        #[label("found {actual}")]
+        location: Location,
+    },
+
+    #[error("infinite type: {var} would be {ty}")]
+    #[diagnostic(
+        code(rinha::check::infinite_type),
+        help("values whose type contains itself, like lists made of nested tuples, need recursive types")
+    )]
+    Infinite {
+        var: Type,
+        ty: Type,
+        #[label("here")]
+        location: Location,
+    },
+
+    #[error("unbound variable `{name}`")]
+    #[diagnostic(code(rinha::check::unbound_variable))]
+    Unbound {
+        name: String,
+        #[label("not defined")]
+        location: Location,
+    },
+
+    #[error("cannot add {lhs} and {rhs}")]
+    #[diagnostic(
+        code(rinha::check::add),
+        help("`+` adds two integers, or concatenates when either side is a string")
+    )]
+    Add {
+        lhs: Type,
+        rhs: Type,
+        #[label]
+        location: Location,
+    },
+}
+
+impl TypeError {
+    /// Location of the term the error is about.
+    pub fn location(&self) -> &Location {
+        match self {
+            TypeError::Mismatch { location, .. }
+            | TypeError::Infinite { location, .. }
+            | TypeError::Unbound { location, .. }
+            | TypeError::Add { location, .. } => location,
+        }
+    }
+}
+
+/// Infer the type of a program, or report all the type errors found in it.
+/// ```rust
+/// use rinha_compiladores::ast::Term;
+/// use rinha_compiladores::check::check;
+///
+/// let source = "let id = fn (x) => { x }; (id(1), id(\"a\"))";
+/// let file = rinha::parser::parse_or_report("example", source).expect("parse error");
+/// let ty = check(&Term::from(file.expression)).expect("type error");
+///
+/// assert_eq!(ty.to_string(), "(int, str)");
+/// ```
+pub fn check(term: &Term) -> Result<Type, Vec<TypeError>> {
+    let mut infer = Infer::default();
+    let ty = infer.program(term);
+    match infer.errors.is_empty() {
+        true => Ok(ty.normalize()),
+        false => Err(infer.errors),
+    }
+}
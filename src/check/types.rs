@@ -0,0 +1,83 @@
+use std::fmt::{Display, Formatter, Result};
+
+/// Type of a rinha value, as inferred by the checker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Str,
+    Bool,
+    Tuple(Box<Type>, Box<Type>),
+    /// Function from its parameters to its result.
+    Fn(Vec<Type>, Box<Type>),
+    /// Type variable, shown as `'a`, `'b`... once normalized.
+    Var(u32),
+}
+
+impl Type {
+    pub fn tuple(first: Type, second: Type) -> Type {
+        Type::Tuple(Box::new(first), Box::new(second))
+    }
+
+    pub fn function(parameters: Vec<Type>, result: Type) -> Type {
+        Type::Fn(parameters, Box::new(result))
+    }
+
+    /// Renumber the type variables from zero, in order of appearance, so
+    /// that they display as `'a`, `'b`...
+    pub fn normalize(&self) -> Type {
+        self.rename(&mut Vec::new())
+    }
+
+    /// Normalize several types at once, sharing the names of their
+    /// variables.
+    pub fn normalize_all(types: &[&Type]) -> Vec<Type> {
+        let mut names = Vec::new();
+        types.iter().map(|t| t.rename(&mut names)).collect()
+    }
+
+    fn rename(&self, names: &mut Vec<u32>) -> Type {
+        match self {
+            Type::Int | Type::Str | Type::Bool => self.clone(),
+            Type::Tuple(first, second) => Type::tuple(first.rename(names), second.rename(names)),
+            Type::Fn(parameters, result) => {
+                let parameters = parameters.iter().map(|p| p.rename(names)).collect();
+                Type::function(parameters, result.rename(names))
+            }
+            Type::Var(var) => match names.iter().position(|n| n == var) {
+                Some(index) => Type::Var(index as u32),
+                None => {
+                    names.push(*var);
+                    Type::Var((names.len() - 1) as u32)
+                }
+            },
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Str => write!(f, "str"),
+            Type::Bool => write!(f, "bool"),
+            Type::Tuple(first, second) => write!(f, "({first}, {second})"),
+            Type::Fn(parameters, result) => {
+                write!(f, "(")?;
+                for (i, parameter) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{parameter}")?;
+                }
+                write!(f, ") -> {result}")
+            }
+            Type::Var(var) => {
+                let letter = (b'a' + (var % 26) as u8) as char;
+                match var / 26 {
+                    0 => write!(f, "'{letter}"),
+                    n => write!(f, "'{letter}{n}"),
+                }
+            }
+        }
+    }
+}
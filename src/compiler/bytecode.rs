@@ -0,0 +1,123 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter, Result};
+use std::rc::Rc;
+
+use crate::ast::{BinaryOp, Location};
+use crate::eval::val::Val;
+
+/// A single VM instruction.
+///
+/// Operands index into the [`Proto`] being executed: `Const` and `Fail`
+/// into its constants pool, `Local` and `SetLocal` into the slots of the
+/// current frame, `Upvalue` into the captures of the running closure and
+/// `Closure` into its nested prototypes. Jump targets are absolute
+/// instruction indexes.
+#[derive(Debug, Clone, Copy)]
+pub enum Op {
+    /// Push a constant.
+    Const(u32),
+    /// Push the value of a local slot.
+    Local(u32),
+    /// Pop the top of the stack into a local slot.
+    SetLocal(u32),
+    /// Push a value captured by the running closure.
+    Upvalue(u32),
+    /// Push the running closure itself, used for recursive references.
+    Current,
+    /// Create a closure from a nested prototype, capturing its upvalues.
+    Closure(u32),
+    /// Capture again the local slots the closure in a local slot captured,
+    /// once all the functions of its recursive group are bound.
+    Fix(u32),
+    /// Pop two operands and push the result of the operator.
+    Binary(BinaryOp),
+    /// Print the top of the stack, leaving it in place.
+    Print,
+    /// Replace the top of the stack by its first element.
+    First,
+    /// Replace the top of the stack by its second element.
+    Second,
+    /// Pop two values and push a tuple made of them.
+    Tuple,
+    /// Jump unconditionally.
+    Jump(u32),
+    /// Pop a boolean and jump if it is `false`.
+    JumpIfFalse(u32),
+    /// Jump if the boolean on top of the stack decides the value of `&&`
+    /// or `||`, leaving it in place as that value.
+    ShortCircuit(BinaryOp, u32),
+    /// Make the running call fail unless it returns a boolean, as the right
+    /// operand of `&&` and `||` in tail position must be.
+    ExpectBool,
+    /// Call the closure below the given number of arguments.
+    Call(u32),
+    /// Like `Call`, but reusing the current frame.
+    TailCall(u32),
+    /// Return the top of the stack to the caller.
+    Return,
+    /// Raise the error message stored in the given constant.
+    Fail(u32),
+    /// Raise an error for the unbound variable named by the given constant.
+    Unbound(u32),
+}
+
+/// Where a closure takes each of its upvalues from when it is created.
+#[derive(Debug, Clone, Copy)]
+pub enum Capture {
+    /// A local slot of the enclosing function.
+    Local(u32),
+    /// An upvalue of the enclosing function.
+    Upvalue(u32),
+    /// The enclosing closure itself.
+    Current,
+}
+
+/// Compiled function: its code, constants and nested functions.
+#[derive(Debug, Default)]
+pub struct Proto {
+    /// Name of the `let` binding the function was defined by, if any.
+    pub name: Option<String>,
+    /// Number of parameters, stored in the first local slots.
+    pub arity: u32,
+    /// Number of local slots, parameters included.
+    pub locals: u32,
+    pub code: Vec<Op>,
+    pub constants: Vec<Val>,
+    pub protos: Vec<Rc<Proto>>,
+    /// How the upvalues are captured when a closure of this function is
+    /// created.
+    pub captures: Vec<Capture>,
+    /// Source locations of the instructions that can fail, sorted by
+    /// instruction index.
+    pub locations: Vec<(u32, Location)>,
+}
+
+impl Proto {
+    /// Location of the instruction at `ip`, used to report runtime errors.
+    pub fn location(&self, ip: usize) -> Location {
+        match self
+            .locations
+            .binary_search_by_key(&(ip as u32), |(at, _)| *at)
+        {
+            Ok(index) => self.locations[index].1.clone(),
+            Err(_) => Location::default(),
+        }
+    }
+}
+
+/// Runtime closure of the VM: a prototype and its captured values.
+///
+/// The upvalues only change when [`Op::Fix`] ties a recursive group.
+pub struct Lambda {
+    pub proto: Rc<Proto>,
+    pub upvalues: RefCell<Vec<Val>>,
+}
+
+impl Debug for Lambda {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match &self.proto.name {
+            Some(name) => write!(f, "Lambda({name})"),
+            None => write!(f, "Lambda"),
+        }
+    }
+}
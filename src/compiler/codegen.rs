@@ -0,0 +1,275 @@
+use std::rc::Rc;
+
+use super::bytecode::{Capture, Op, Proto};
+use crate::ast::{BinaryOp, Element, Function, Let, Location, Term, RED_ZONE, STACK_SIZE};
+use crate::eval::val::Val;
+
+/// Compile a whole program into the prototype of its top-level function.
+/// ```rust
+/// use rinha_compiladores::ast::Term;
+/// use rinha_compiladores::codegen::compile;
+/// use rinha_compiladores::vm::run;
+///
+/// let file = rinha::parser::parse_or_report("example", "1 + 1").expect("parse error");
+/// let proto = compile(&Term::from(file.expression));
+/// let result = run(proto).expect("error on execution");
+///
+/// assert_eq!(format!("{:?}", result), "Int(2)");
+/// ```
+pub fn compile(term: &Term) -> Rc<Proto> {
+    let mut compiler = Compiler {
+        functions: vec![FnState::new(None)],
+    };
+    compiler.term(term, true);
+    compiler.emit(Op::Return);
+    let script = compiler
+        .functions
+        .pop()
+        .expect("missing top-level function");
+    Rc::new(script.proto)
+}
+
+/// Function being compiled, with the names currently in scope.
+struct FnState {
+    proto: Proto,
+    /// Local bindings in scope, innermost last.
+    scope: Vec<(String, u32)>,
+    /// Names of the upvalues, parallel to `proto.captures`.
+    upvalues: Vec<String>,
+}
+
+impl FnState {
+    fn new(name: Option<String>) -> Self {
+        Self {
+            proto: Proto {
+                name,
+                ..Default::default()
+            },
+            scope: Vec::new(),
+            upvalues: Vec::new(),
+        }
+    }
+
+    fn declare(&mut self, name: &str) -> u32 {
+        let slot = self.proto.locals;
+        self.proto.locals += 1;
+        self.scope.push((name.to_string(), slot));
+        slot
+    }
+}
+
+struct Compiler {
+    /// Functions being compiled, the innermost last.
+    functions: Vec<FnState>,
+}
+
+impl Compiler {
+    fn current(&mut self) -> &mut FnState {
+        self.functions
+            .last_mut()
+            .expect("no function being compiled")
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        let code = &mut self.current().proto.code;
+        code.push(op);
+        code.len() - 1
+    }
+
+    /// Emit an instruction that can fail, recording where it comes from.
+    fn emit_at(&mut self, op: Op, location: &Location) -> usize {
+        let at = self.emit(op);
+        let locations = &mut self.current().proto.locations;
+        locations.push((at as u32, location.clone()));
+        at
+    }
+
+    fn patch(&mut self, at: usize) {
+        let code = &mut self.current().proto.code;
+        let target = code.len() as u32;
+        match &mut code[at] {
+            Op::Jump(to) | Op::JumpIfFalse(to) | Op::ShortCircuit(_, to) => *to = target,
+            op => unreachable!("cannot patch {:?}", op),
+        }
+    }
+
+    fn constant(&mut self, val: Val) -> u32 {
+        let constants = &mut self.current().proto.constants;
+        constants.push(val);
+        (constants.len() - 1) as u32
+    }
+
+    /// Find where `name` lives from the point of view of the function at
+    /// `level`, registering upvalues along the way if it is captured.
+    fn resolve(&mut self, level: usize, name: &str) -> Option<Capture> {
+        let state = &self.functions[level];
+        if let Some((_, slot)) = state.scope.iter().rev().find(|(n, _)| n == name) {
+            return Some(Capture::Local(*slot));
+        }
+        if let Some(index) = state.upvalues.iter().position(|n| n == name) {
+            return Some(Capture::Upvalue(index as u32));
+        }
+        if state.proto.name.as_deref() == Some(name) {
+            return Some(Capture::Current);
+        }
+        if level == 0 {
+            return None;
+        }
+        let source = self.resolve(level - 1, name)?;
+        let state = &mut self.functions[level];
+        state.upvalues.push(name.to_string());
+        state.proto.captures.push(source);
+        Some(Capture::Upvalue((state.upvalues.len() - 1) as u32))
+    }
+
+    /// Whether `name` is bound where the code being compiled is.
+    fn visible(&self, name: &str) -> bool {
+        self.functions.iter().any(|state| {
+            state.scope.iter().any(|(n, _)| n == name) || state.proto.name.as_deref() == Some(name)
+        })
+    }
+
+    fn function(&mut self, f: &Function, name: Option<String>) {
+        let mut state = FnState::new(name);
+        state.proto.arity = f.parameters.len() as u32;
+        for param in &f.parameters {
+            state.declare(&param.text);
+        }
+        self.functions.push(state);
+        self.term(&f.value, true);
+        self.emit(Op::Return);
+        let state = self.functions.pop().expect("missing function");
+
+        let protos = &mut self.current().proto.protos;
+        protos.push(Rc::new(state.proto));
+        let index = (protos.len() - 1) as u32;
+        self.emit(Op::Closure(index));
+    }
+
+    /// Compile `let`s defining functions that can all call each other, see
+    /// [`Let::group`]. A closure only captures the siblings created before
+    /// it, so the group is tied with `Fix` once all of them are bound.
+    fn group(&mut self, group: &[&Let], tail: bool) {
+        let slots: Vec<_> = group
+            .iter()
+            .map(|l| self.current().declare(&l.name.text))
+            .collect();
+        for (l, &slot) in group.iter().zip(&slots) {
+            let Term::Function(f) = l.value.as_ref() else {
+                unreachable!("not a function");
+            };
+            self.function(f, Some(l.name.text.clone()));
+            self.emit(Op::SetLocal(slot));
+        }
+        if group.len() > 1 {
+            for &slot in &slots {
+                self.emit(Op::Fix(slot));
+            }
+        }
+        let last = group.last().expect("empty group");
+        self.term(&last.next, tail);
+        for _ in group {
+            self.current().scope.pop();
+        }
+    }
+
+    /// Compile a term leaving its value on top of the stack. Calls in
+    /// `tail` position reuse the frame of the current function.
+    fn term(&mut self, term: &Term, tail: bool) {
+        // Compiling recurses as deep as the program is nested.
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || match term {
+            Term::Int(number) => {
+                let index = self.constant(Val::Int(number.value));
+                self.emit(Op::Const(index));
+            }
+            Term::Str(str) => {
+                let index = self.constant(Val::Str(str.value.clone()));
+                self.emit(Op::Const(index));
+            }
+            Term::Bool(bool) => {
+                let index = self.constant(Val::Bool(bool.value));
+                self.emit(Op::Const(index));
+            }
+            Term::Var(v) => {
+                let level = self.functions.len() - 1;
+                match self.resolve(level, &v.text) {
+                    Some(Capture::Local(slot)) => self.emit(Op::Local(slot)),
+                    Some(Capture::Upvalue(index)) => self.emit(Op::Upvalue(index)),
+                    Some(Capture::Current) => self.emit(Op::Current),
+                    None => {
+                        let index = self.constant(Val::Str(v.text.clone()));
+                        self.emit_at(Op::Unbound(index), &v.location)
+                    }
+                };
+            }
+            Term::Let(l) => {
+                let group = l.group(|name| self.visible(name));
+                if !group.is_empty() {
+                    return self.group(&group, tail);
+                }
+                self.term(&l.value, false);
+                let slot = self.current().declare(&l.name.text);
+                self.emit(Op::SetLocal(slot));
+                self.term(&l.next, tail);
+                self.current().scope.pop();
+            }
+            Term::If(i) => {
+                self.term(&i.condition, false);
+                let otherwise = self.emit_at(Op::JumpIfFalse(0), i.condition.location());
+                self.term(&i.then, tail);
+                let end = self.emit(Op::Jump(0));
+                self.patch(otherwise);
+                self.term(&i.otherwise, tail);
+                self.patch(end);
+            }
+            Term::Binary(bin) if matches!(bin.op, BinaryOp::And | BinaryOp::Or) => {
+                self.term(&bin.lhs, false);
+                let end = self.emit_at(Op::ShortCircuit(bin.op, 0), &bin.location);
+                if tail {
+                    self.emit_at(Op::ExpectBool, &bin.location);
+                }
+                self.term(&bin.rhs, tail);
+                self.emit_at(Op::Binary(bin.op), &bin.location);
+                self.patch(end);
+            }
+            Term::Binary(bin) => {
+                self.term(&bin.lhs, false);
+                self.term(&bin.rhs, false);
+                self.emit_at(Op::Binary(bin.op), &bin.location);
+            }
+            Term::Function(f) => self.function(f, None),
+            Term::Call(call) => {
+                self.term(&call.callee, false);
+                for arg in &call.arguments {
+                    self.term(arg, false);
+                }
+                let argc = call.arguments.len() as u32;
+                match tail {
+                    true => self.emit_at(Op::TailCall(argc), &call.location),
+                    false => self.emit_at(Op::Call(argc), &call.location),
+                };
+            }
+            Term::Print(print) => {
+                self.term(&print.value, false);
+                self.emit_at(Op::Print, &print.location);
+            }
+            Term::First(f) => {
+                self.term(&f.value, false);
+                self.emit_at(Op::First, &f.location);
+            }
+            Term::Second(s) => {
+                self.term(&s.value, false);
+                self.emit_at(Op::Second, &s.location);
+            }
+            Term::Tuple(t) => {
+                self.term(&t.first, false);
+                self.term(&t.second, false);
+                self.emit_at(Op::Tuple, &t.location);
+            }
+            Term::Error(e) => {
+                let index = self.constant(Val::Str(e.message.clone()));
+                self.emit_at(Op::Fail(index), &e.location);
+            }
+        })
+    }
+}
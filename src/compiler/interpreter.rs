@@ -1,29 +1,278 @@
-use std::{collections::HashMap, io::Error};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
-use crate::{ast::Term, eval::core::eval};
+use miette::{Diagnostic, NamedSource};
+use rinha::parser::ParseError;
+use thiserror::Error;
 
-#[allow(dead_code)]
-/// Interpreter function
+use crate::ast::{File, Location, Term};
+use crate::eval::convert::{ConversionError, FromVal};
+use crate::eval::core::{eval_top_level, output_error};
+use crate::eval::env::Env;
+use crate::eval::error::RuntimeError;
+use crate::eval::eval_binary::Arithmetic;
+use crate::eval::eval_call::apply;
+use crate::eval::eval_call::Application;
+use crate::eval::limits::Limits;
+use crate::eval::native::Native;
+use crate::eval::output::{Buffer, Output};
+use crate::eval::runtime::{Interrupt, Runtime};
+use crate::eval::val::Val;
+
+/// A program run by [`interpreter`]: its value and what it printed.
+#[derive(Debug)]
+pub struct Evaluation {
+    pub value: Val,
+    /// The lines printed by the program, each ending with a newline.
+    pub output: String,
+}
+
+/// Shows the value as `print` would.
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+/// Error of a program run by an [`Interpreter`].
+#[derive(Debug, Error, Diagnostic)]
+pub enum InterpreterError {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] ParseError),
+
+    /// Runtime error in a program the interpreter has the source of, which
+    /// it renders with a snippet of.
+    #[error("{error}")]
+    #[diagnostic(forward(error))]
+    Runtime {
+        error: Box<RuntimeError>,
+        #[source_code]
+        source_code: NamedSource,
+    },
+
+    /// Runtime error in a program given parsed, see
+    /// [`Interpreter::eval_file`].
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Unsourced(RuntimeError),
+
+    /// The result of [`Interpreter::call`] is not of the type asked for.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Conversion(#[from] ConversionError),
+}
+
+impl InterpreterError {
+    /// The runtime error, if the program ran.
+    pub fn runtime_error(&self) -> Option<&RuntimeError> {
+        match self {
+            InterpreterError::Parse(_) | InterpreterError::Conversion(_) => None,
+            InterpreterError::Runtime { error, .. } => Some(error),
+            InterpreterError::Unsourced(error) => Some(error),
+        }
+    }
+}
+
+/// Rinha embedded in a Rust program.
+///
+/// The `let`s a program starts with stay bound for the next programs the
+/// interpreter runs, next to the values and native functions it was built
+/// with.
 /// ```rust
-/// use rinha_compiladores::interpreter::interpreter;
+/// use rinha_compiladores::interpreter::Interpreter;
+/// use rinha_compiladores::val::Val;
 ///
-/// let result = interpreter("1 + 1".to_string().as_str()).expect("error");
-/// assert_eq!(result, "Int(2)".to_string());
-pub fn interpreter(source: &str) -> Result<String, Error> {
-    let source = rinha::parser::parse_or_report("Terminal", source).expect("parse error");
-    let term = source.expression;
-    let mut scope = HashMap::new();
-
-    let term = Term::from(term);
-
-    match eval(term.clone(), &mut scope) {
-        Ok(val) => {
-            if format!("{:?}", term).starts_with("Print(Print") {
-                return Ok("".to_string());
-            }
-
-            Ok(format!("{:?}", val))
+/// let mut interpreter = Interpreter::builder()
+///     .function("double", 1, |args| match args {
+///         [Val::Int(n)] => Ok(Val::Int(n * 2)),
+///         _ => Err("expected an int".to_string()),
+///     })
+///     .build();
+/// interpreter.eval("let quadruple = fn (n) => { double(double(n)) }; 0").expect("error");
+///
+/// let value = interpreter.eval("quadruple(5)").expect("error");
+/// assert_eq!(value.to_string(), "20");
+/// ```
+pub struct Interpreter {
+    env: Env,
+    rt: Runtime,
+    /// Text of the programs run so far, by the name they were parsed with,
+    /// to show errors in.
+    sources: HashMap<String, String>,
+}
+
+/// Configuration of an [`Interpreter`], see [`Interpreter::builder`].
+#[derive(Default)]
+pub struct InterpreterBuilder {
+    env: Env,
+    rt: Runtime,
+}
+
+impl InterpreterBuilder {
+    /// Where `print` writes to, buffered stdout by default.
+    pub fn output(mut self, output: impl Output + 'static) -> Self {
+        self.rt.output = Box::new(output);
+        self
+    }
+
+    /// Whether to memoize calls to pure functions, see
+    /// [`Memo`](crate::eval::memo::Memo).
+    pub fn memo(mut self, enabled: bool) -> Self {
+        self.rt.memo.enabled = enabled;
+        self
+    }
+
+    /// Resources each program, or each [`Interpreter::call`], may use.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.rt.limits = limits;
+        self
+    }
+
+    /// What arithmetic does on overflow, see [`Arithmetic`].
+    pub fn arithmetic(mut self, arithmetic: Arithmetic) -> Self {
+        self.rt.arithmetic = arithmetic;
+        self
+    }
+
+    /// What calls missing arguments do, see [`Application`].
+    pub fn application(mut self, application: Application) -> Self {
+        self.rt.application = application;
+        self
+    }
+
+    /// Flag stopping the running program when set, see [`Interrupt`].
+    pub fn interrupt(mut self, interrupt: Interrupt) -> Self {
+        self.rt.interrupt = interrupt;
+        self
+    }
+
+    /// Bind a name to a value for every program.
+    pub fn define(mut self, name: impl Into<String>, value: Val) -> Self {
+        self.env = self.env.bind(name, value);
+        self
+    }
+
+    /// Bind a name to a function of `arity` arguments implemented in Rust,
+    /// see [`Native`].
+    pub fn function(
+        self,
+        name: impl Into<String>,
+        arity: usize,
+        body: impl Fn(&[Val]) -> Result<Val, String> + 'static,
+    ) -> Self {
+        let name = name.into();
+        let native = Native::new(name.clone(), arity, body);
+        self.define(name, Val::Native(native.into()))
+    }
+
+    pub fn build(self) -> Interpreter {
+        Interpreter {
+            env: self.env,
+            rt: self.rt,
+            sources: HashMap::new(),
         }
-        Err(e) => Err(Error::new(std::io::ErrorKind::Other, format!("{:?}", e))),
     }
 }
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::builder().build()
+    }
+}
+
+impl Interpreter {
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
+    /// Parse and run a program.
+    pub fn eval(&mut self, source: &str) -> Result<Val, InterpreterError> {
+        let name = format!("<input:{}>", self.sources.len() + 1);
+        let file = rinha::parser::parse_or_report(&name, source)?;
+        self.sources.insert(name, source.to_string());
+        self.run(Term::from(file.expression))
+    }
+
+    /// Run a parsed program. Errors come without source, which the
+    /// interpreter does not have.
+    pub fn eval_file(&mut self, file: File) -> Result<Val, InterpreterError> {
+        self.run(file.expression)
+    }
+
+    fn run(&mut self, term: Term) -> Result<Val, InterpreterError> {
+        let (value, env) =
+            eval_top_level(term, &self.env, &mut self.rt).map_err(|e| self.error(e))?;
+        self.env = env;
+        Ok(value)
+    }
+
+    /// Call a function of a program on Rust arguments, converting its
+    /// result. Errors of the call itself, like missing arguments, have no
+    /// location.
+    /// ```rust
+    /// use rinha_compiladores::convert::IntoVal;
+    /// use rinha_compiladores::interpreter::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::default();
+    /// let swap = interpreter.eval("fn (pair) => { (second(pair), first(pair)) }").expect("error");
+    /// let swapped: (String, i32) = interpreter.call(&swap, vec![(1, "a").into_val()]).expect("error");
+    ///
+    /// assert_eq!(swapped, ("a".to_string(), 1));
+    /// ```
+    pub fn call<R: FromVal>(&mut self, f: &Val, args: Vec<Val>) -> Result<R, InterpreterError> {
+        let result = apply(f, args, &Location::default(), &mut self.rt);
+        let flushed = self.rt.output.flush();
+        let value = result.map_err(|e| self.error(e))?;
+        flushed.map_err(|e| self.error(output_error(e, &Location::default())))?;
+        Ok(R::from_val(&value)?)
+    }
+
+    /// Attach to a runtime error the source of the program it happened in.
+    fn error(&self, error: RuntimeError) -> InterpreterError {
+        let source = error
+            .location()
+            .and_then(|l| Some((&l.filename, self.sources.get(&l.filename)?)));
+        match source {
+            Some((name, text)) => InterpreterError::Runtime {
+                source_code: NamedSource::new(name, text.clone()),
+                error: Box::new(error),
+            },
+            None => InterpreterError::Unsourced(error),
+        }
+    }
+
+    /// Value bound to a name, by the interpreter or by the programs it ran.
+    pub fn get(&self, name: &str) -> Option<Val> {
+        self.env.get(name)
+    }
+
+    /// Bind a name to a value for the next programs.
+    pub fn define(&mut self, name: impl Into<String>, value: Val) {
+        self.env = self.env.bind(name, value);
+    }
+
+    /// State of the evaluations, e.g. to read the memoization statistics.
+    pub fn runtime(&mut self) -> &mut Runtime {
+        &mut self.rt
+    }
+}
+
+/// Run a program, capturing what it prints.
+/// ```rust
+/// use rinha_compiladores::interpreter::interpreter;
+///
+/// let evaluation = interpreter("let _ = print(1 + 1); (\"a\", true)").expect("error");
+///
+/// assert_eq!(evaluation.output, "2\n");
+/// assert_eq!(evaluation.to_string(), "(a, true)");
+/// ```
+pub fn interpreter(source: &str) -> Result<Evaluation, InterpreterError> {
+    let output = Buffer::default();
+    let mut interpreter = Interpreter::builder().output(output.clone()).build();
+    let value = interpreter.eval(source)?;
+    Ok(Evaluation {
+        value,
+        output: output.take(),
+    })
+}
@@ -1 +1,4 @@
+pub mod bytecode;
+pub mod codegen;
 pub mod interpreter;
+pub mod vm;
@@ -0,0 +1,411 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::bytecode::{Capture, Lambda, Op, Proto};
+use crate::ast::Location;
+use crate::eval::core::{first, output_error, second};
+use crate::eval::error::RuntimeError;
+use crate::eval::eval_binary::{apply_bin, expect_bool, short_circuit, Arithmetic};
+use crate::eval::eval_call::Application;
+use crate::eval::limits::{Limit, Limits, Usage};
+use crate::eval::output::{Output, Stdout};
+use crate::eval::resolve::UnboundVariable;
+use crate::eval::val::{Tuple, Val};
+
+/// Value local slots hold before the `let` that owns them runs.
+const UNSET: Val = Val::Bool(false);
+
+/// Run a compiled program and return the value of its top-level function.
+/// ```rust
+/// use rinha_compiladores::ast::Term;
+/// use rinha_compiladores::codegen::compile;
+/// use rinha_compiladores::vm::run;
+///
+/// let file = rinha::parser::parse_or_report("example", "(1, true)").expect("parse error");
+/// let result = run(compile(&Term::from(file.expression))).expect("error on execution");
+///
+/// assert_eq!(format!("{}", result), "(1, true)");
+/// ```
+pub fn run(proto: Rc<Proto>) -> Result<Val, RuntimeError> {
+    Vm::default().run(proto)
+}
+
+/// Activation record of a closure being executed.
+struct Frame {
+    lambda: Rc<Lambda>,
+    ip: usize,
+    /// Stack index of the first local slot. The closure itself sits just
+    /// below it.
+    base: usize,
+    /// Closure and instruction of the last [`Op::ExpectBool`] run by this
+    /// call or the ones it replaced as tail calls.
+    expect_bool: Option<(Rc<Lambda>, usize)>,
+}
+
+impl Frame {
+    /// Location of the instruction being executed.
+    fn location(&self) -> Location {
+        self.lambda.proto.location(self.ip - 1)
+    }
+}
+
+/// Stack machine executing the bytecode produced by
+/// [`compile`](super::codegen::compile).
+///
+/// Calls push frames on a heap-allocated stack, so deeply recursive
+/// programs are not limited by the native stack.
+pub struct Vm {
+    stack: Vec<Val>,
+    frames: Vec<Frame>,
+    output: Box<dyn Output>,
+    limits: Limits,
+    usage: Usage,
+    arithmetic: Arithmetic,
+    application: Application,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Vm::new(Box::new(Stdout::default()))
+    }
+}
+
+impl Vm {
+    /// A VM printing to `output`.
+    pub fn new(output: Box<dyn Output>) -> Self {
+        Vm {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            output,
+            limits: Limits::default(),
+            usage: Usage::default(),
+            arithmetic: Arithmetic::default(),
+            application: Application::default(),
+        }
+    }
+
+    /// Set the resources each run may use.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set what arithmetic does on overflow.
+    pub fn with_arithmetic(mut self, arithmetic: Arithmetic) -> Self {
+        self.arithmetic = arithmetic;
+        self
+    }
+
+    /// Set what calls missing arguments do.
+    pub fn with_application(mut self, application: Application) -> Self {
+        self.application = application;
+        self
+    }
+
+    /// Resources used by the last run.
+    pub fn usage(&self) -> &Usage {
+        &self.usage
+    }
+
+    pub fn run(&mut self, proto: Rc<Proto>) -> Result<Val, RuntimeError> {
+        self.usage.start(&self.limits);
+        let result = self.execute(proto);
+        // What the program printed goes out whether it failed or not.
+        let flushed = self.output.flush();
+        let val = result?;
+        flushed.map_err(|e| output_error(e, &Location::default()))?;
+        Ok(val)
+    }
+
+    fn execute(&mut self, proto: Rc<Proto>) -> Result<Val, RuntimeError> {
+        let script = Rc::new(Lambda {
+            proto,
+            upvalues: RefCell::default(),
+        });
+        self.stack.clear();
+        self.frames.clear();
+        self.stack.push(Val::Lambda(script));
+        let lambda = self
+            .enter(0, 0, Location::default)?
+            .expect("the script takes no arguments");
+        let mut frame = Frame {
+            lambda,
+            ip: 0,
+            base: 1,
+            expect_bool: None,
+        };
+
+        loop {
+            let op = frame.lambda.proto.code[frame.ip];
+            frame.ip += 1;
+            match op {
+                Op::Const(index) => {
+                    let val = frame.lambda.proto.constants[index as usize].clone();
+                    self.stack.push(val);
+                }
+                Op::Local(slot) => {
+                    let val = self.stack[frame.base + slot as usize].clone();
+                    self.stack.push(val);
+                }
+                Op::SetLocal(slot) => {
+                    let val = self.pop();
+                    self.stack[frame.base + slot as usize] = val;
+                }
+                Op::Upvalue(index) => {
+                    let val = frame.lambda.upvalues.borrow()[index as usize].clone();
+                    self.stack.push(val);
+                }
+                Op::Current => self.stack.push(Val::Lambda(frame.lambda.clone())),
+                Op::Closure(index) => {
+                    let proto = frame.lambda.proto.protos[index as usize].clone();
+                    let upvalues = proto
+                        .captures
+                        .iter()
+                        .map(|capture| match *capture {
+                            Capture::Local(slot) => self.stack[frame.base + slot as usize].clone(),
+                            Capture::Upvalue(index) => {
+                                frame.lambda.upvalues.borrow()[index as usize].clone()
+                            }
+                            Capture::Current => Val::Lambda(frame.lambda.clone()),
+                        })
+                        .collect();
+                    self.stack.push(Val::Lambda(Rc::new(Lambda {
+                        proto,
+                        upvalues: RefCell::new(upvalues),
+                    })));
+                }
+                Op::Fix(slot) => {
+                    let Val::Lambda(lambda) = &self.stack[frame.base + slot as usize] else {
+                        unreachable!("not a closure");
+                    };
+                    let mut upvalues = lambda.upvalues.borrow_mut();
+                    for (upvalue, capture) in upvalues.iter_mut().zip(&lambda.proto.captures) {
+                        if let Capture::Local(local) = *capture {
+                            *upvalue = self.stack[frame.base + local as usize].clone();
+                        }
+                    }
+                }
+                Op::Binary(op) => {
+                    let rhs = self.pop();
+                    let lhs = self.pop();
+                    let val = apply_bin(op, lhs, rhs, self.arithmetic, || frame.location())?;
+                    if let Val::Str(_) = val {
+                        self.usage
+                            .allocate(&self.limits, &val)
+                            .map_err(|limit| exceeded(limit, &frame))?;
+                    }
+                    self.stack.push(val);
+                }
+                Op::Print => {
+                    let val = self.stack.last().expect("empty stack");
+                    if let Err(e) = self.output.print(val) {
+                        return Err(output_error(e, &frame.location()));
+                    }
+                }
+                Op::First => {
+                    let val = first(self.pop(), &frame.location())?;
+                    self.stack.push(val);
+                }
+                Op::Second => {
+                    let val = second(self.pop(), &frame.location())?;
+                    self.stack.push(val);
+                }
+                Op::Tuple => {
+                    let second = self.pop();
+                    let first = self.pop();
+                    let val = Val::Tuple(Tuple {
+                        f: Rc::new(first),
+                        s: Rc::new(second),
+                    });
+                    self.usage
+                        .allocate(&self.limits, &val)
+                        .map_err(|limit| exceeded(limit, &frame))?;
+                    self.stack.push(val);
+                }
+                Op::Jump(target) => frame.ip = target as usize,
+                Op::JumpIfFalse(target) => match self.pop() {
+                    Val::Bool(true) => {}
+                    Val::Bool(false) => frame.ip = target as usize,
+                    val => {
+                        return Err(RuntimeError::TypeMismatch {
+                            expected: "bool",
+                            actual: val.type_name(),
+                            location: frame.location(),
+                        })
+                    }
+                },
+                Op::ShortCircuit(op, target) => {
+                    let lhs = self.stack.last().expect("empty stack");
+                    if short_circuit(op, lhs, || frame.location())?.is_some() {
+                        frame.ip = target as usize;
+                    }
+                }
+                Op::Call(argc) => {
+                    self.call(&frame, true)?;
+                    let callee = self.stack.len() - argc as usize - 1;
+                    let Some(lambda) = self.enter(callee, argc as usize, || frame.location())?
+                    else {
+                        continue;
+                    };
+                    let caller = std::mem::replace(
+                        &mut frame,
+                        Frame {
+                            lambda,
+                            ip: 0,
+                            base: callee + 1,
+                            expect_bool: None,
+                        },
+                    );
+                    self.frames.push(caller);
+                }
+                Op::TailCall(argc) => {
+                    self.call(&frame, false)?;
+                    let callee = self.stack.len() - argc as usize - 1;
+                    let slot = frame.base - 1;
+                    self.stack.drain(slot..callee);
+                    let Some(lambda) = self.enter(slot, argc as usize, || frame.location())? else {
+                        // The partial application is returned right away.
+                        match self.ret(&mut frame)? {
+                            Some(result) => return Ok(result),
+                            None => continue,
+                        }
+                    };
+                    frame = Frame {
+                        lambda,
+                        ip: 0,
+                        base: slot + 1,
+                        expect_bool: frame.expect_bool.take(),
+                    };
+                }
+                Op::ExpectBool => frame.expect_bool = Some((frame.lambda.clone(), frame.ip - 1)),
+                Op::Return => {
+                    if let Some(result) = self.ret(&mut frame)? {
+                        return Ok(result);
+                    }
+                }
+                Op::Fail(index) => {
+                    let message = &frame.lambda.proto.constants[index as usize];
+                    return Err(RuntimeError::User {
+                        message: message.to_string(),
+                        location: frame.location(),
+                    });
+                }
+                Op::Unbound(index) => {
+                    let name = &frame.lambda.proto.constants[index as usize];
+                    return Err(RuntimeError::Unbound {
+                        variables: vec![UnboundVariable {
+                            name: name.to_string(),
+                            location: frame.location(),
+                        }],
+                    });
+                }
+            }
+        }
+    }
+
+    /// Count a call made from `frame`, `nested` in it rather than
+    /// replacing it as a tail call.
+    fn call(&mut self, frame: &Frame, nested: bool) -> Result<(), RuntimeError> {
+        // The current frame is not in `frames`.
+        let depth = self.frames.len() + 1 + nested as usize;
+        self.usage
+            .call(&self.limits, depth)
+            .map_err(|limit| exceeded(limit, frame))
+    }
+
+    fn pop(&mut self) -> Val {
+        self.stack.pop().expect("empty stack")
+    }
+
+    /// Return the top of the stack from `frame` to its caller, which
+    /// becomes the current frame. The result of the program when `frame`
+    /// is the top-level one.
+    fn ret(&mut self, frame: &mut Frame) -> Result<Option<Val>, RuntimeError> {
+        let mut result = self.pop();
+        if let Some((lambda, ip)) = frame.expect_bool.take() {
+            result = expect_bool(result, || lambda.proto.location(ip))?;
+        }
+        self.stack.truncate(frame.base - 1);
+        match self.frames.pop() {
+            Some(caller) => {
+                *frame = caller;
+                self.stack.push(result);
+                Ok(None)
+            }
+            None => Ok(Some(result)),
+        }
+    }
+
+    /// Check that the value at `callee` is a closure accepting `argc`
+    /// arguments and make room for its local slots above them. `location`
+    /// is only called to report a failure.
+    ///
+    /// With partial application, a closure missing arguments is replaced
+    /// by its partial application together with them, and `None` returned.
+    fn enter(
+        &mut self,
+        callee: usize,
+        argc: usize,
+        location: impl FnOnce() -> Location,
+    ) -> Result<Option<Rc<Lambda>>, RuntimeError> {
+        let lambda = match &self.stack[callee] {
+            Val::Lambda(lambda) => lambda.clone(),
+            val => {
+                return Err(RuntimeError::TypeMismatch {
+                    expected: "closure",
+                    actual: val.type_name(),
+                    location: location(),
+                })
+            }
+        };
+        let arity = lambda.proto.arity as usize;
+        if argc < arity && self.application == Application::Partial {
+            let args = self.stack.split_off(callee + 1);
+            self.stack[callee] = partial(lambda, args, location());
+            return Ok(None);
+        }
+        if argc != arity {
+            return Err(RuntimeError::Arity {
+                expected: arity,
+                actual: argc,
+                location: location(),
+            });
+        }
+        self.stack
+            .resize(callee + 1 + lambda.proto.locals as usize, UNSET);
+        Ok(Some(lambda))
+    }
+}
+
+/// Closure taking the missing arguments of `lambda` and calling it on
+/// `args` followed by them, reporting the call at `location`.
+fn partial(lambda: Rc<Lambda>, args: Vec<Val>, location: Location) -> Val {
+    let given = args.len() as u32;
+    let arity = lambda.proto.arity;
+    let mut code = vec![Op::Upvalue(0)];
+    code.extend((1..=given).map(Op::Upvalue));
+    code.extend((0..arity - given).map(Op::Local));
+    code.push(Op::TailCall(arity));
+    code.push(Op::Return);
+    let proto = Proto {
+        name: None,
+        arity: arity - given,
+        locals: arity - given,
+        locations: vec![(code.len() as u32 - 2, location)],
+        code,
+        ..Default::default()
+    };
+    let mut upvalues = vec![Val::Lambda(lambda)];
+    upvalues.extend(args);
+    Val::Lambda(Rc::new(Lambda {
+        proto: Rc::new(proto),
+        upvalues: RefCell::new(upvalues),
+    }))
+}
+
+fn exceeded(limit: Limit, frame: &Frame) -> RuntimeError {
+    RuntimeError::LimitExceeded {
+        limit,
+        location: frame.location(),
+    }
+}
@@ -0,0 +1,170 @@
+use std::rc::Rc;
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::val::{Tuple, Val};
+
+/// A value that does not have the shape of the Rust type it was converted
+/// to.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Diagnostic)]
+#[error("expected {expected}, found {found}")]
+#[diagnostic(code(rinha::conversion))]
+pub struct ConversionError {
+    /// Rinha type of the Rust type, like `(int, str)`.
+    pub expected: String,
+    /// Rinha type of the value.
+    pub found: String,
+}
+
+/// Rust values programs can be given.
+/// ```rust
+/// use rinha_compiladores::convert::IntoVal;
+///
+/// assert_eq!((1, ("a", true)).into_val().to_string(), "(1, (a, true))");
+/// ```
+pub trait IntoVal {
+    fn into_val(self) -> Val;
+}
+
+/// Rust values programs can return.
+/// ```rust
+/// use rinha_compiladores::convert::FromVal;
+/// use rinha_compiladores::convert::IntoVal;
+///
+/// let val = (1, "a").into_val();
+///
+/// assert_eq!(<(i32, String)>::from_val(&val), Ok((1, "a".to_string())));
+/// assert_eq!(
+///     <(i32, i32)>::from_val(&val).unwrap_err().to_string(),
+///     "expected (int, int), found (int, str)"
+/// );
+/// ```
+pub trait FromVal: Sized {
+    fn from_val(val: &Val) -> Result<Self, ConversionError>;
+
+    /// Rinha type of the values converted to `Self`, for errors.
+    fn type_name() -> String;
+}
+
+/// Rinha type of a value, with the types of the elements of tuples.
+fn shape(mut val: &Val) -> String {
+    let mut shapes = String::new();
+    let mut depth = 0;
+    while let Val::Tuple(t) = val {
+        shapes += &format!("({}, ", shape(&t.f));
+        (val, depth) = (&t.s, depth + 1);
+    }
+    format!("{shapes}{}{}", val.type_name(), ")".repeat(depth))
+}
+
+fn mismatch<T: FromVal>(val: &Val) -> ConversionError {
+    ConversionError {
+        expected: T::type_name(),
+        found: shape(val),
+    }
+}
+
+impl IntoVal for Val {
+    fn into_val(self) -> Val {
+        self
+    }
+}
+
+impl IntoVal for i32 {
+    fn into_val(self) -> Val {
+        Val::Int(self)
+    }
+}
+
+impl IntoVal for bool {
+    fn into_val(self) -> Val {
+        Val::Bool(self)
+    }
+}
+
+impl IntoVal for String {
+    fn into_val(self) -> Val {
+        Val::Str(self)
+    }
+}
+
+impl IntoVal for &str {
+    fn into_val(self) -> Val {
+        Val::Str(self.to_string())
+    }
+}
+
+impl<A: IntoVal, B: IntoVal> IntoVal for (A, B) {
+    fn into_val(self) -> Val {
+        Val::Tuple(Tuple {
+            f: Rc::new(self.0.into_val()),
+            s: Rc::new(self.1.into_val()),
+        })
+    }
+}
+
+/// Any value, as is.
+impl FromVal for Val {
+    fn from_val(val: &Val) -> Result<Self, ConversionError> {
+        Ok(val.clone())
+    }
+
+    fn type_name() -> String {
+        "any".to_string()
+    }
+}
+
+impl FromVal for i32 {
+    fn from_val(val: &Val) -> Result<Self, ConversionError> {
+        match val {
+            Val::Int(n) => Ok(*n),
+            val => Err(mismatch::<Self>(val)),
+        }
+    }
+
+    fn type_name() -> String {
+        "int".to_string()
+    }
+}
+
+impl FromVal for bool {
+    fn from_val(val: &Val) -> Result<Self, ConversionError> {
+        match val {
+            Val::Bool(b) => Ok(*b),
+            val => Err(mismatch::<Self>(val)),
+        }
+    }
+
+    fn type_name() -> String {
+        "bool".to_string()
+    }
+}
+
+impl FromVal for String {
+    fn from_val(val: &Val) -> Result<Self, ConversionError> {
+        match val {
+            Val::Str(s) => Ok(s.clone()),
+            val => Err(mismatch::<Self>(val)),
+        }
+    }
+
+    fn type_name() -> String {
+        "str".to_string()
+    }
+}
+
+impl<A: FromVal, B: FromVal> FromVal for (A, B) {
+    fn from_val(val: &Val) -> Result<Self, ConversionError> {
+        let converted = match val {
+            Val::Tuple(t) => A::from_val(&t.f).and_then(|a| Ok((a, B::from_val(&t.s)?))),
+            val => return Err(mismatch::<Self>(val)),
+        };
+        // Report the whole tuple rather than the element that failed.
+        converted.map_err(|_| mismatch::<Self>(val))
+    }
+
+    fn type_name() -> String {
+        format!("({}, {})", A::type_name(), B::type_name())
+    }
+}
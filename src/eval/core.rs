@@ -1,119 +1,337 @@
-use std::cell::RefCell;
 use std::rc::Rc;
-use std::{collections::HashMap, io::Error};
 
-use super::eval_binary::eval_bin;
-use super::eval_call::eval_call;
+use super::env::{Env, Frame};
+use super::error::RuntimeError;
+use super::eval_binary::{eval_bin, expect_bool, short_circuit};
+use super::eval_call::{prepare_call, Prepared};
+use super::memo::MemoKey;
+use super::resolve::{resolve, Expr, Function};
+use super::runtime::Runtime;
 use super::val::{Tuple, Val};
-use crate::ast::{Let, Print, Term};
+use crate::ast::{BinaryOp, Element, Location, Term, RED_ZONE, STACK_SIZE};
 
 /// Evaluate a term and return a value
+///
+/// Terms in tail position (the `next` of a `Let`, the branches of an `If`,
+/// the right operand of `&&` and `||` and the body of a called function)
+/// are evaluated by looping instead of recursing, so tail-recursive
+/// programs run in constant native stack.
 /// ```rust
 /// use rinha_compiladores::core::eval;
 /// use rinha_compiladores::ast;
-/// use std::collections::HashMap;
+/// use rinha_compiladores::env::Env;
 ///
 /// let result = eval(
 ///     ast::Term::Int(ast::Int {
 ///         value: 1,
 ///         ..Default::default()
 ///     }),
-///     &mut HashMap::new(),
+///     &Env::new(),
 /// ).expect("error on evaluation");
 ///
 /// assert_eq!(format!("{:?}", result), format!("{:?}", rinha_compiladores::val::Val::Int(1)));
+/// ```
+pub fn eval(term: Term, env: &Env) -> Result<Val, RuntimeError> {
+    eval_with(term, env, &mut Runtime::default())
+}
+
+/// Evaluate a term using the given runtime state, e.g. to control
+/// memoization or inspect its statistics afterwards.
 ///
-pub fn eval(term: Term, scope: &mut HashMap<String, Val>) -> Result<Val, Error> {
-    match term {
-        Term::Int(number) => Ok(Val::Int(number.value)),
-        Term::Str(str) => Ok(Val::Str(str.value)),
-        Term::Bool(bool) => Ok(Val::Bool(bool.value)),
-        Term::Print(print) => eval_print(print, scope),
-        Term::Binary(bin) => eval_bin(bin, scope),
-        Term::If(i) => match eval(*i.condition, scope) {
-            Ok(Val::Bool(true)) => eval(*i.then, scope),
-            Ok(Val::Bool(false)) => eval(*i.otherwise, scope),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "invalid type")),
-        },
-        Term::Let(l) => eval_let(l, scope),
-        Term::Var(v) => match scope.get(&v.text) {
-            Some(val) => Ok(val.clone()),
-            None => Err(Error::new(
-                std::io::ErrorKind::Other,
-                format!("variável não definida {:?}", v),
-            )),
-        },
-        Term::Function(f) => {
-            let env = Rc::new(RefCell::new(scope.clone()));
-            Ok(Val::Closure { f, env })
-        }
-        Term::Call(call) => eval_call(call, scope),
-        Term::Error(e) => Err(Error::new(std::io::ErrorKind::Other, e.message)),
-        Term::First(f) => match eval(*f.value, scope) {
-            Ok(Val::Str(s)) => Ok(Val::Str(s.chars().next().unwrap().to_string())),
-            Ok(Val::Tuple(t)) => Ok(*t.f),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "invalid type")),
-        },
-        Term::Second(s) => match eval(*s.value, scope) {
-            Ok(Val::Str(s)) => Ok(Val::Str(s.chars().nth(1).unwrap().to_string())),
-            Ok(Val::Tuple(t)) => Ok(*t.s),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "invalid type")),
-        },
-        Term::Tuple(t) => {
-            let first = eval(*t.first, scope).unwrap();
-            let second = eval(*t.second, scope).unwrap();
-            Ok(Val::Tuple(Tuple {
-                f: Box::new(first),
-                s: Box::new(second),
-            }))
-        }
+/// The term is resolved first, so a program using unbound variables fails
+/// before running, listing all of them.
+pub fn eval_with(term: Term, env: &Env, rt: &mut Runtime) -> Result<Val, RuntimeError> {
+    run_script(&term, env, rt).map(|(val, _, _)| val)
+}
+
+/// Resolve a term as a script with the bindings of `env` as globals and run
+/// it, returning its value along with the script and the frame it ran in.
+fn run_script(
+    term: &Term,
+    env: &Env,
+    rt: &mut Runtime,
+) -> Result<(Val, Rc<Function>, Rc<Frame>), RuntimeError> {
+    let (names, values): (Vec<_>, Vec<_>) = env.bindings().into_iter().unzip();
+    let script = resolve(term, &names).map_err(|variables| RuntimeError::Unbound { variables })?;
+    let globals = Frame::new(values, None);
+    let frame = Frame::enter(&globals, Vec::new(), script.slots);
+    rt.start();
+    let result = exec_body(&script.body, &frame, rt);
+    // What the program printed goes out whether it failed or not.
+    let flushed = rt.output.flush();
+    let val = result?;
+    flushed.map_err(|e| output_error(e, term.location()))?;
+    Ok((val, script, frame))
+}
+
+/// Evaluate a term like [`eval_with`], also returning `env` extended with the
+/// `let`s the term starts with, so that a later term can use them.
+///
+/// Functions bound this way keep referring to each other, and to
+/// themselves, as they did in the term.
+/// ```rust
+/// use rinha_compiladores::ast::Term;
+/// use rinha_compiladores::core::{eval, eval_top_level};
+/// use rinha_compiladores::env::Env;
+/// use rinha_compiladores::runtime::Runtime;
+///
+/// let parse = |source| Term::from(rinha::parser::parse_or_report("example", source).expect("parse error").expression);
+/// let fact = parse("let fact = fn (n) => { if (n == 0) { 1 } else { n * fact(n - 1) } }; 0");
+/// let (_, env) = eval_top_level(fact, &Env::new(), &mut Runtime::default()).expect("error");
+///
+/// assert_eq!(eval(parse("fact(5)"), &env).expect("error").to_string(), "120");
+/// ```
+pub fn eval_top_level(term: Term, env: &Env, rt: &mut Runtime) -> Result<(Val, Env), RuntimeError> {
+    let (val, script, frame) = run_script(&term, env, rt)?;
+
+    // The leading `let`s of the term are in the frame of the script, in the
+    // slots of the leading `Let`s of its body.
+    let mut env = env.clone();
+    let (mut term, mut expr) = (&term, &script.body);
+    while let (Term::Let(l), Expr::Let { slot, next, .. }) = (term, expr.as_ref()) {
+        env = env.bind(&l.name.text, frame.get(0, *slot));
+        (term, expr) = (&l.next, next);
     }
+    Ok((val, env))
 }
 
-fn eval_let(l: Let, scope: &mut HashMap<String, Val>) -> Result<Val, Error> {
-    let name = l.name.text;
-    match eval(*l.value, scope)? {
-        Val::Closure { f, env } => {
-            let closure = Val::Closure {
-                f,
-                env: env.clone(),
-            };
-            env.borrow_mut().insert(name.clone(), closure.clone());
-            scope.insert(name, closure);
+/// Evaluate a resolved expression in the given frame.
+pub fn exec(expr: &Rc<Expr>, frame: &Rc<Frame>, rt: &mut Runtime) -> Result<Val, RuntimeError> {
+    run(expr, frame, rt, false)
+}
+
+/// Evaluate the body of the script or of a function called from Rust,
+/// which takes a call of its own for the depth limit, like the frames of
+/// the VM.
+pub fn exec_body(
+    expr: &Rc<Expr>,
+    frame: &Rc<Frame>,
+    rt: &mut Runtime,
+) -> Result<Val, RuntimeError> {
+    run(expr, frame, rt, true)
+}
+
+/// Evaluate an expression, `called` when it is the body of a call in
+/// progress. The first call it enters otherwise counts for the depth, the
+/// later ones replace it as tail calls.
+///
+/// Evaluations nest as deep as the calls in progress, so they continue on a
+/// new stack segment when the current one runs out.
+fn run(
+    expr: &Rc<Expr>,
+    frame: &Rc<Frame>,
+    rt: &mut Runtime,
+    called: bool,
+) -> Result<Val, RuntimeError> {
+    stacker::maybe_grow(RED_ZONE, STACK_SIZE, || run_on(expr, frame, rt, called))
+}
+
+fn run_on(
+    expr: &Rc<Expr>,
+    frame: &Rc<Frame>,
+    rt: &mut Runtime,
+    mut called: bool,
+) -> Result<Val, RuntimeError> {
+    let mut expr = expr.clone();
+    let mut frame = frame.clone();
+    // Memoized calls entered through tail calls. They all return the value
+    // this evaluation ends with.
+    let mut pending: Vec<MemoKey> = Vec::new();
+    // The last `&&` or `||` whose right operand is being evaluated in tail
+    // position. Its value must be a boolean, as the ones of the earlier ones.
+    let mut operand: Option<Rc<Expr>> = None;
+    if called {
+        rt.enter();
+    }
+    let result = loop {
+        expr = match expr.as_ref() {
+            Expr::Int(number) => break Ok(Val::Int(*number)),
+            Expr::Str(str) => break Ok(Val::Str(str.clone())),
+            Expr::Bool(bool) => break Ok(Val::Bool(*bool)),
+            Expr::Var { depth, slot } => break Ok(frame.get(*depth, *slot)),
+            Expr::Print { value, location } => break eval_print(value, location, &frame, rt),
+            Expr::Binary {
+                op: op @ (BinaryOp::And | BinaryOp::Or),
+                lhs,
+                rhs,
+                location,
+            } => match exec(lhs, &frame, rt)
+                .and_then(|lhs| short_circuit(*op, &lhs, || location.clone()))
+            {
+                Ok(Some(val)) => break Ok(val),
+                Ok(None) => {
+                    operand = Some(expr.clone());
+                    rhs.clone()
+                }
+                Err(e) => break Err(e),
+            },
+            Expr::Binary {
+                op,
+                lhs,
+                rhs,
+                location,
+            } => break eval_bin(*op, lhs, rhs, location, &frame, rt),
+            Expr::If {
+                condition,
+                then,
+                otherwise,
+                location,
+            } => match exec(condition, &frame, rt) {
+                Ok(Val::Bool(true)) => then.clone(),
+                Ok(Val::Bool(false)) => otherwise.clone(),
+                Ok(val) => {
+                    break Err(RuntimeError::TypeMismatch {
+                        expected: "bool",
+                        actual: val.type_name(),
+                        location: location.clone(),
+                    })
+                }
+                Err(e) => break Err(e),
+            },
+            Expr::Let { slot, value, next } => match exec(value, &frame, rt) {
+                Ok(val) => {
+                    frame.set(*slot, val);
+                    next.clone()
+                }
+                Err(e) => break Err(e),
+            },
+            Expr::Function(f) => {
+                break Ok(Val::Closure {
+                    f: f.clone(),
+                    env: frame,
+                })
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                location,
+            } => {
+                // Every long running program calls functions, checking
+                // there is enough to stop them.
+                if let Err(e) = rt.call(location, !called) {
+                    break Err(e);
+                }
+                match prepare_call(callee, arguments, location, &frame, rt) {
+                    Ok(Prepared::Done(val)) => break Ok(val),
+                    Ok(Prepared::Enter {
+                        body,
+                        frame: callee_frame,
+                        key,
+                    }) => {
+                        if !called {
+                            rt.enter();
+                            called = true;
+                        }
+                        pending.extend(key);
+                        frame = callee_frame;
+                        body
+                    }
+                    Err(e) => break Err(e),
+                }
+            }
+            Expr::Error { message, location } => {
+                break Err(RuntimeError::User {
+                    message: message.clone(),
+                    location: location.clone(),
+                })
+            }
+            Expr::First { value, location } => {
+                break exec(value, &frame, rt).and_then(|val| first(val, location))
+            }
+            Expr::Second { value, location } => {
+                break exec(value, &frame, rt).and_then(|val| second(val, location))
+            }
+            Expr::Tuple {
+                first,
+                second,
+                location,
+            } => {
+                break exec(first, &frame, rt).and_then(|first| {
+                    let second = exec(second, &frame, rt)?;
+                    let val = Val::Tuple(Tuple {
+                        f: Rc::new(first),
+                        s: Rc::new(second),
+                    });
+                    rt.allocate(&val, location)?;
+                    Ok(val)
+                })
+            }
+        };
+    };
+    if called {
+        rt.leave();
+    }
+    let result = match operand.as_deref() {
+        Some(Expr::Binary { location, .. }) => {
+            result.and_then(|val| expect_bool(val, || location.clone()))
         }
-        val => {
-            scope.insert(name, val);
+        _ => result,
+    };
+
+    if let Ok(val) = &result {
+        for key in pending {
+            rt.memo.insert(key, val.clone());
         }
     }
-    eval(*l.next, scope)
+    result
+}
+
+fn eval_print(
+    value: &Rc<Expr>,
+    location: &Location,
+    frame: &Rc<Frame>,
+    rt: &mut Runtime,
+) -> Result<Val, RuntimeError> {
+    let val = exec(value, frame, rt)?;
+    rt.output
+        .print(&val)
+        .map_err(|e| output_error(e, location))?;
+    Ok(val)
 }
 
-fn eval_print(print: Print, scope: &mut HashMap<String, Val>) -> Result<Val, Error> {
-    let val = eval(*print.value, scope);
+/// Error for an [`Output`](super::output::Output) failing.
+pub fn output_error(error: std::io::Error, location: &Location) -> RuntimeError {
+    RuntimeError::Output {
+        message: error.to_string(),
+        location: location.clone(),
+    }
+}
+
+/// `first` of a tuple, or the first character of a string.
+pub fn first(val: Val, location: &Location) -> Result<Val, RuntimeError> {
     match val {
-        Ok(Val::Int(n)) => {
-            println!("{n}");
-            Ok(Val::Int(n))
-        }
-        Ok(Val::Bool(b)) => {
-            println!("{b}");
-            Ok(Val::Bool(b))
-        }
-        Ok(Val::Str(s)) => {
-            println!("{s}");
-            Ok(Val::Str(s))
-        }
-        Ok(Val::Tuple(t)) => {
-            println!("{t}");
-            Ok(Val::Tuple(t))
-        }
-        Ok(Val::Closure { f, env }) => {
-            println!("<#closure>");
-            Ok(Val::Closure { f, env })
-        }
-        _ => Err(Error::new(
-            std::io::ErrorKind::Other,
-            format!("Print: invalid type {:?}", val),
-        )),
+        Val::Tuple(t) => Ok((*t.f).clone()),
+        Val::Str(s) => character(&s, 0, location),
+        val => Err(not_a_tuple(&val, location)),
+    }
+}
+
+/// `second` of a tuple, or the second character of a string.
+pub fn second(val: Val, location: &Location) -> Result<Val, RuntimeError> {
+    match val {
+        Val::Tuple(t) => Ok((*t.s).clone()),
+        Val::Str(s) => character(&s, 1, location),
+        val => Err(not_a_tuple(&val, location)),
+    }
+}
+
+fn character(s: &str, index: usize, location: &Location) -> Result<Val, RuntimeError> {
+    match s.chars().nth(index) {
+        Some(c) => Ok(Val::Str(c.to_string())),
+        None => Err(RuntimeError::StringTooShort {
+            position: ["first", "second"][index],
+            length: s.chars().count(),
+            location: location.clone(),
+        }),
+    }
+}
+
+/// Error for `first` or `second` applied to something else than a tuple
+/// or a string.
+fn not_a_tuple(val: &Val, location: &Location) -> RuntimeError {
+    RuntimeError::TypeMismatch {
+        expected: "tuple",
+        actual: val.type_name(),
+        location: location.clone(),
     }
 }
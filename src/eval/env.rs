@@ -0,0 +1,139 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter, Result};
+use std::rc::Rc;
+
+use super::val::Val;
+
+/// Bindings a program is evaluated with.
+///
+/// It is a persistent linked list: binding a name creates a new environment
+/// on top of the current one without copying it.
+/// ```rust
+/// use rinha_compiladores::env::Env;
+/// use rinha_compiladores::val::Val;
+///
+/// let outer = Env::new().bind("x", Val::Int(1));
+/// let inner = outer.bind("x", Val::Int(2));
+///
+/// assert_eq!(format!("{:?}", inner.get("x")), "Some(Int(2))");
+/// assert_eq!(format!("{:?}", outer.get("x")), "Some(Int(1))");
+/// ```
+#[derive(Clone, Default)]
+pub struct Env(Option<Rc<Binding>>);
+
+struct Binding {
+    name: String,
+    value: Val,
+    parent: Env,
+}
+
+impl Env {
+    /// Creates an empty environment.
+    pub fn new() -> Self {
+        Self(None)
+    }
+
+    /// Returns a new environment where `name` is bound to `value`.
+    pub fn bind(&self, name: impl Into<String>, value: Val) -> Env {
+        Env(Some(Rc::new(Binding {
+            name: name.into(),
+            value,
+            parent: self.clone(),
+        })))
+    }
+
+    /// Looks up the innermost binding of `name`.
+    pub fn get(&self, name: &str) -> Option<Val> {
+        let mut env = self;
+        while let Some(binding) = &env.0 {
+            if binding.name == name {
+                return Some(binding.value.clone());
+            }
+            env = &binding.parent;
+        }
+        None
+    }
+
+    /// Names visible in this environment, innermost first. Shadowed names
+    /// are listed once.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        let mut env = self;
+        while let Some(binding) = &env.0 {
+            if !names.contains(&binding.name) {
+                names.push(binding.name.clone());
+            }
+            env = &binding.parent;
+        }
+        names
+    }
+
+    /// Every binding, outermost first, shadowed ones included.
+    pub fn bindings(&self) -> Vec<(String, Val)> {
+        let mut bindings = Vec::new();
+        let mut env = self;
+        while let Some(binding) = &env.0 {
+            bindings.push((binding.name.clone(), binding.value.clone()));
+            env = &binding.parent;
+        }
+        bindings.reverse();
+        bindings
+    }
+}
+
+impl Debug for Env {
+    /// Only the names are shown: closures usually live in the environment
+    /// they capture, so printing values would never end.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Activation record of a resolved function: the values of its slots and
+/// the frame of the function it was defined in.
+///
+/// Closures capture the frame they are created in, which costs O(1)
+/// regardless of how many names are in scope.
+pub struct Frame {
+    slots: RefCell<Vec<Val>>,
+    parent: Option<Rc<Frame>>,
+}
+
+impl Frame {
+    pub fn new(slots: Vec<Val>, parent: Option<Rc<Frame>>) -> Rc<Frame> {
+        Rc::new(Self {
+            slots: RefCell::new(slots),
+            parent,
+        })
+    }
+
+    /// Frame of a call: the arguments followed by `slots - args.len()`
+    /// slots for the `let`s of the body, which are filled as they run.
+    pub fn enter(parent: &Rc<Frame>, mut args: Vec<Val>, slots: usize) -> Rc<Frame> {
+        args.resize(slots, Val::Bool(false));
+        Frame::new(args, Some(parent.clone()))
+    }
+
+    /// The frame `depth` functions out from this one.
+    pub fn ancestor(&self, depth: usize) -> &Frame {
+        let mut frame = self;
+        for _ in 0..depth {
+            frame = frame.parent.as_deref().expect("frame depth out of range");
+        }
+        frame
+    }
+
+    pub fn get(&self, depth: usize, slot: usize) -> Val {
+        self.ancestor(depth).slots.borrow()[slot].clone()
+    }
+
+    pub fn set(&self, slot: usize, val: Val) {
+        self.slots.borrow_mut()[slot] = val;
+    }
+}
+
+impl Debug for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "Frame({} slots)", self.slots.borrow().len())
+    }
+}
@@ -0,0 +1,159 @@
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::limits::Limit;
+use super::resolve::UnboundVariable;
+use crate::ast::Location;
+
+/// Error raised while running a program, by either backend.
+///
+/// Every variant carries the location of the node that failed, so it can
+/// be rendered with a snippet of the source by [`miette`].
+/// ```rust
+/// use rinha_compiladores::core::eval;
+/// use rinha_compiladores::env::Env;
+/// use rinha_compiladores::error::RuntimeError;
+///
+/// let file = rinha::parser::parse_or_report("example", "1 - true").expect("parse error");
+/// let error = eval(file.expression.into(), &Env::new()).unwrap_err();
+///
+/// assert!(matches!(error, RuntimeError::TypeMismatch { expected: "int", actual: "bool", .. }));
+/// ```
+#[derive(Debug, Clone, Error, Diagnostic)]
+pub enum RuntimeError {
+    /// Variables used where no binding of their name is visible. They are
+    /// all reported before the program runs.
+    #[error("{}", unbound_message(.variables))]
+    #[diagnostic(code(rinha::unbound_variable))]
+    Unbound {
+        #[related]
+        variables: Vec<UnboundVariable>,
+    },
+
+    #[error("type mismatch: expected {expected}, found {actual}")]
+    #[diagnostic(code(rinha::type_mismatch))]
+    TypeMismatch {
+        expected: &'static str,
+        actual: &'static str,
+        #[label("found {actual}")]
+        location: Location,
+    },
+
+    #[error("division by zero")]
+    #[diagnostic(code(rinha::division_by_zero))]
+    DivisionByZero {
+        #[label("the divisor is zero")]
+        location: Location,
+    },
+
+    #[error("expected {expected} arguments, got {actual}")]
+    #[diagnostic(code(rinha::arity_mismatch))]
+    Arity {
+        expected: usize,
+        actual: usize,
+        #[label("called with {actual} arguments")]
+        location: Location,
+    },
+
+    /// An error node of the program was evaluated.
+    #[error("{message}")]
+    #[diagnostic(code(rinha::error))]
+    User {
+        message: String,
+        #[label]
+        location: Location,
+    },
+
+    /// A [`Native`](super::native::Native) function failed.
+    #[error("{name}: {message}")]
+    #[diagnostic(code(rinha::native))]
+    Native {
+        name: String,
+        message: String,
+        #[label("in this call")]
+        location: Location,
+    },
+
+    /// The evaluation was stopped through its
+    /// [`Interrupt`](super::runtime::Interrupt).
+    #[error("interrupted")]
+    #[diagnostic(code(rinha::interrupted))]
+    Interrupted {
+        #[label("stopped at this call")]
+        location: Location,
+    },
+
+    /// The output of `print` failed, e.g. a closed pipe.
+    #[error("could not print: {message}")]
+    #[diagnostic(code(rinha::output))]
+    Output {
+        message: String,
+        #[label("while printing this")]
+        location: Location,
+    },
+
+    /// The evaluation exceeded one of its [`Limits`](super::limits::Limits).
+    #[error("exceeded the {limit}")]
+    #[diagnostic(code(rinha::limit_exceeded))]
+    LimitExceeded {
+        limit: Limit,
+        #[label("while running this")]
+        location: Location,
+    },
+
+    /// `==`, `<` and the like were applied to closures, which have no
+    /// meaningful equality.
+    #[error("closures cannot be compared")]
+    #[diagnostic(code(rinha::incomparable))]
+    Incomparable {
+        #[label("comparing closures")]
+        location: Location,
+    },
+
+    /// `first` or `second` was applied to a string without the character
+    /// it takes.
+    #[error("no {position} character in a string of length {length}")]
+    #[diagnostic(code(rinha::string_too_short))]
+    StringTooShort {
+        position: &'static str,
+        length: usize,
+        #[label("this string is too short")]
+        location: Location,
+    },
+
+    #[error("integer overflow")]
+    #[diagnostic(code(rinha::overflow))]
+    Overflow {
+        #[label("the result does not fit in 32 bits")]
+        location: Location,
+    },
+}
+
+impl RuntimeError {
+    /// Location of the node that failed, the first unbound variable for
+    /// [`RuntimeError::Unbound`].
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            RuntimeError::Unbound { variables } => variables.first().map(|v| &v.location),
+            RuntimeError::TypeMismatch { location, .. }
+            | RuntimeError::DivisionByZero { location }
+            | RuntimeError::Arity { location, .. }
+            | RuntimeError::User { location, .. }
+            | RuntimeError::Native { location, .. }
+            | RuntimeError::Interrupted { location }
+            | RuntimeError::Output { location, .. }
+            | RuntimeError::LimitExceeded { location, .. }
+            | RuntimeError::Incomparable { location }
+            | RuntimeError::StringTooShort { location, .. }
+            | RuntimeError::Overflow { location } => Some(location),
+        }
+    }
+}
+
+fn unbound_message(variables: &[UnboundVariable]) -> String {
+    let names: Vec<_> = variables.iter().map(|v| format!("`{}`", v.name)).collect();
+    match names.len() {
+        1 => format!("unbound variable {}", names[0]),
+        _ => format!("unbound variables {}", names.join(", ")),
+    }
+}
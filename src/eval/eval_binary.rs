@@ -1,78 +1,256 @@
-use std::{collections::HashMap, io::Error};
+use std::cmp::Ordering;
+use std::rc::Rc;
 
-use super::{core::eval, val::Val};
-use crate::ast::{Binary, BinaryOp};
+use super::env::Frame;
+use super::error::RuntimeError;
+use super::resolve::Expr;
+use super::runtime::Runtime;
+use super::{core::exec, val::Val};
+use crate::ast::{BinaryOp, Location};
 
-pub fn eval_bin(bin: Binary, scope: &mut HashMap<String, Val>) -> Result<Val, Error> {
-    let lhs = eval(*bin.lhs, scope);
-    let rhs = eval(*bin.rhs, scope);
-    match bin.op {
+pub fn eval_bin(
+    op: BinaryOp,
+    lhs: &Rc<Expr>,
+    rhs: &Rc<Expr>,
+    location: &Location,
+    frame: &Rc<Frame>,
+    rt: &mut Runtime,
+) -> Result<Val, RuntimeError> {
+    let lhs = exec(lhs, frame, rt)?;
+    if let Some(val) = short_circuit(op, &lhs, || location.clone())? {
+        return Ok(val);
+    }
+    let rhs = exec(rhs, frame, rt)?;
+    let val = apply_bin(op, lhs, rhs, rt.arithmetic, || location.clone())?;
+    if let Val::Str(_) = val {
+        rt.allocate(&val, location)?;
+    }
+    Ok(val)
+}
+
+/// What `+`, `-`, `*`, `/` and `%` do with results that do not fit in an
+/// `i32`, like `2147483647 + 1` or `(0 - 2147483647 - 1) / (0 - 1)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Arithmetic {
+    /// Fail with [`RuntimeError::Overflow`].
+    #[default]
+    Checked,
+    /// Wrap around, in two's complement.
+    Wrapping,
+}
+
+impl Arithmetic {
+    /// Apply an arithmetic operator to ints, `None` when the result
+    /// overflows. The divisor of `/` and `%` must not be zero.
+    fn apply(self, op: BinaryOp, a: i32, b: i32) -> Option<i32> {
+        match self {
+            Arithmetic::Checked => match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Sub => a.checked_sub(b),
+                BinaryOp::Mul => a.checked_mul(b),
+                BinaryOp::Div => a.checked_div(b),
+                BinaryOp::Rem => a.checked_rem(b),
+                op => unreachable!("{op:?} is not arithmetic"),
+            },
+            Arithmetic::Wrapping => Some(match op {
+                BinaryOp::Add => a.wrapping_add(b),
+                BinaryOp::Sub => a.wrapping_sub(b),
+                BinaryOp::Mul => a.wrapping_mul(b),
+                BinaryOp::Div => a.wrapping_div(b),
+                BinaryOp::Rem => a.wrapping_rem(b),
+                op => unreachable!("{op:?} is not arithmetic"),
+            }),
+        }
+    }
+
+    /// [`Arithmetic::apply`] failing at `location` on overflow and on
+    /// division by zero.
+    fn int(
+        self,
+        op: BinaryOp,
+        a: i32,
+        b: i32,
+        location: impl FnOnce() -> Location,
+    ) -> Result<Val, RuntimeError> {
+        if b == 0 && matches!(op, BinaryOp::Div | BinaryOp::Rem) {
+            return Err(RuntimeError::DivisionByZero {
+                location: location(),
+            });
+        }
+        match self.apply(op, a, b) {
+            Some(n) => Ok(Val::Int(n)),
+            None => Err(RuntimeError::Overflow {
+                location: location(),
+            }),
+        }
+    }
+}
+
+/// The value of `&&` and `||` when their left operand `lhs` decides it,
+/// `None` when the right operand must be evaluated, and of other operators
+/// always. `location` is only called when `lhs` is not a boolean.
+pub fn short_circuit(
+    op: BinaryOp,
+    lhs: &Val,
+    location: impl FnOnce() -> Location,
+) -> Result<Option<Val>, RuntimeError> {
+    let decisive = match op {
+        BinaryOp::And => false,
+        BinaryOp::Or => true,
+        _ => return Ok(None),
+    };
+    match lhs {
+        Val::Bool(b) if *b == decisive => Ok(Some(Val::Bool(*b))),
+        Val::Bool(_) => Ok(None),
+        lhs => Err(RuntimeError::TypeMismatch {
+            expected: "bool",
+            actual: lhs.type_name(),
+            location: location(),
+        }),
+    }
+}
+
+/// The value of `&&` or `||` whose left operand did not decide it: its
+/// right operand, which must be a boolean. `location` is only called when
+/// it is not.
+pub fn expect_bool(rhs: Val, location: impl FnOnce() -> Location) -> Result<Val, RuntimeError> {
+    match rhs {
+        Val::Bool(_) => Ok(rhs),
+        rhs => Err(RuntimeError::TypeMismatch {
+            expected: "bool",
+            actual: rhs.type_name(),
+            location: location(),
+        }),
+    }
+}
+
+/// Apply a binary operator to two already evaluated operands.
+///
+/// Shared by the tree walker and the bytecode VM so both backends agree
+/// on the semantics of every operator. `location` is only called when the
+/// operation fails, to locate the error.
+pub fn apply_bin(
+    op: BinaryOp,
+    lhs: Val,
+    rhs: Val,
+    arithmetic: Arithmetic,
+    location: impl FnOnce() -> Location,
+) -> Result<Val, RuntimeError> {
+    match op {
         BinaryOp::Add => match (lhs, rhs) {
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Int(a + b)),
-            (Ok(s), Ok(b)) => Ok(Val::Str(format!("{s}{b}"))),
-            a => Err(Error::new(
-                std::io::ErrorKind::Other,
-                format!("tipo inválido {:?}", a),
-            )),
+            (Val::Int(a), Val::Int(b)) => arithmetic.int(op, a, b, location),
+            (s, b) => Ok(Val::Str(format!("{s}{b}"))),
         },
         BinaryOp::Sub => match (lhs, rhs) {
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Int(a - b)),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
-        },
-        BinaryOp::Lt => match (lhs, rhs) {
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Bool(a < b)),
-            (Ok(Val::Str(a)), Ok(Val::Str(b))) => Ok(Val::Bool(a < b)),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
+            (Val::Int(a), Val::Int(b)) => arithmetic.int(op, a, b, location),
+            (lhs, rhs) => Err(mismatch("int", &lhs, &rhs, location)),
         },
+        BinaryOp::Lt => Ok(Val::Bool(compare(&lhs, &rhs, location)?.is_lt())),
         BinaryOp::Div => match (lhs, rhs) {
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Int(a / b)),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
+            (Val::Int(a), Val::Int(b)) => arithmetic.int(op, a, b, location),
+            (lhs, rhs) => Err(mismatch("int", &lhs, &rhs, location)),
         },
         BinaryOp::Mul => match (lhs, rhs) {
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Int(a * b)),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
+            (Val::Int(a), Val::Int(b)) => arithmetic.int(op, a, b, location),
+            (lhs, rhs) => Err(mismatch("int", &lhs, &rhs, location)),
         },
         BinaryOp::And => match (lhs, rhs) {
-            (Ok(Val::Bool(a)), Ok(Val::Bool(b))) => Ok(Val::Bool(a && b)),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
+            (Val::Bool(a), Val::Bool(b)) => Ok(Val::Bool(a && b)),
+            (lhs, rhs) => Err(mismatch("bool", &lhs, &rhs, location)),
         },
         BinaryOp::Or => match (lhs, rhs) {
-            (Ok(Val::Bool(a)), Ok(Val::Bool(b))) => Ok(Val::Bool(a || b)),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
-        },
-        BinaryOp::Eq => match (lhs, rhs) {
-            (Ok(Val::Bool(a)), Ok(Val::Bool(b))) => Ok(Val::Bool(a == b)),
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Bool(a == b)),
-            (Ok(Val::Str(a)), Ok(Val::Str(b))) => Ok(Val::Bool(a == b)),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
-        },
-        BinaryOp::Gt => match (lhs, rhs) {
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Bool(a > b)),
-            (Ok(Val::Str(a)), Ok(Val::Str(b))) => Ok(Val::Bool(a > b)),
-            _ => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
-        },
-        BinaryOp::Gte => match (lhs, rhs) {
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Bool(a >= b)),
-            (Ok(Val::Str(a)), Ok(Val::Str(b))) => Ok(Val::Bool(a >= b)),
-            (_, _) => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
-        },
-        BinaryOp::Lte => match (lhs, rhs) {
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Bool(a <= b)),
-            (Ok(Val::Str(a)), Ok(Val::Str(b))) => Ok(Val::Bool(a <= b)),
-            (_, _) => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
+            (Val::Bool(a), Val::Bool(b)) => Ok(Val::Bool(a || b)),
+            (lhs, rhs) => Err(mismatch("bool", &lhs, &rhs, location)),
         },
+        BinaryOp::Eq => Ok(Val::Bool(compare(&lhs, &rhs, location)?.is_eq())),
+        BinaryOp::Gt => Ok(Val::Bool(compare(&lhs, &rhs, location)?.is_gt())),
+        BinaryOp::Gte => Ok(Val::Bool(compare(&lhs, &rhs, location)?.is_ge())),
+        BinaryOp::Lte => Ok(Val::Bool(compare(&lhs, &rhs, location)?.is_le())),
         BinaryOp::Rem => match (lhs, rhs) {
-            (_, Ok(Val::Int(0))) => Err(Error::new(std::io::ErrorKind::Other, "Divisão por zero")),
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Int(a % b)),
-            (_, _) => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
-        },
-        BinaryOp::Neq => match (lhs, rhs) {
-            (Ok(Val::Bool(a)), Ok(Val::Bool(b))) => Ok(Val::Bool(a != b)),
-            (Ok(Val::Int(a)), Ok(Val::Int(b))) => Ok(Val::Bool(a != b)),
-            (Ok(Val::Str(a)), Ok(Val::Str(b))) => Ok(Val::Bool(a != b)),
-            (Ok(Val::Str(a)), Ok(Val::Int(b))) => Ok(Val::Bool(a != b.to_string())),
-            (Ok(Val::Int(a)), Ok(Val::Str(b))) => Ok(Val::Bool(a.to_string() != b)),
-            (_, _) => Err(Error::new(std::io::ErrorKind::Other, "Invalid operators")),
+            (Val::Int(a), Val::Int(b)) => arithmetic.int(op, a, b, location),
+            (lhs, rhs) => Err(mismatch("int", &lhs, &rhs, location)),
         },
+        BinaryOp::Neq => Ok(Val::Bool(compare(&lhs, &rhs, location)?.is_ne())),
+    }
+}
+
+/// Type error for operands that are not both of the `expected` type,
+/// reporting the first one that is not.
+fn mismatch(
+    expected: &'static str,
+    lhs: &Val,
+    rhs: &Val,
+    location: impl FnOnce() -> Location,
+) -> RuntimeError {
+    let actual = match lhs.type_name() == expected {
+        true => rhs.type_name(),
+        false => lhs.type_name(),
+    };
+    RuntimeError::TypeMismatch {
+        expected,
+        actual,
+        location: location(),
+    }
+}
+
+/// Compare two values the way `==`, `!=`, `<`, `>`, `<=` and `>=` do.
+///
+/// Both must have the same type: ints, booleans (`false` first) and
+/// strings compare by value, tuples element by element, the first ones
+/// first, stopping at the first difference. Closures cannot be compared.
+/// `location` is only called when the values cannot be compared.
+/// ```rust
+/// use std::cmp::Ordering;
+/// use rinha_compiladores::ast::Location;
+/// use rinha_compiladores::convert::IntoVal;
+/// use rinha_compiladores::eval_binary::compare;
+///
+/// let (a, b) = ((1, "b").into_val(), (1, "a").into_val());
+/// assert_eq!(compare(&a, &b, Location::default).unwrap(), Ordering::Greater);
+/// assert!(compare(&a, &1.into_val(), Location::default).is_err());
+/// ```
+pub fn compare(
+    lhs: &Val,
+    rhs: &Val,
+    location: impl FnOnce() -> Location,
+) -> Result<Ordering, RuntimeError> {
+    match order(lhs, rhs) {
+        Ok(ordering) => Ok(ordering),
+        Err(Incomparable::Closures) => Err(RuntimeError::Incomparable {
+            location: location(),
+        }),
+        Err(Incomparable::Types(expected, actual)) => Err(RuntimeError::TypeMismatch {
+            expected,
+            actual,
+            location: location(),
+        }),
+    }
+}
+
+/// Why two values cannot be compared.
+enum Incomparable {
+    Closures,
+    /// Values of different types, the left one's first.
+    Types(&'static str, &'static str),
+}
+
+fn order(mut lhs: &Val, mut rhs: &Val) -> Result<Ordering, Incomparable> {
+    let closure = |val: &Val| matches!(val, Val::Closure { .. } | Val::Lambda(_) | Val::Native(_));
+    // Second elements are walked in a loop, like `Tuple` does.
+    loop {
+        return match (lhs, rhs) {
+            (Val::Int(a), Val::Int(b)) => Ok(a.cmp(b)),
+            (Val::Bool(a), Val::Bool(b)) => Ok(a.cmp(b)),
+            (Val::Str(a), Val::Str(b)) => Ok(a.cmp(b)),
+            (Val::Tuple(a), Val::Tuple(b)) => match order(&a.f, &b.f)? {
+                Ordering::Equal => {
+                    (lhs, rhs) = (&a.s, &b.s);
+                    continue;
+                }
+                ordering => Ok(ordering),
+            },
+            (lhs, rhs) if closure(lhs) || closure(rhs) => Err(Incomparable::Closures),
+            (lhs, rhs) => Err(Incomparable::Types(lhs.type_name(), rhs.type_name())),
+        };
     }
 }
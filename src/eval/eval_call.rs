@@ -1,20 +1,215 @@
-use std::{collections::HashMap, io::Error};
+use std::rc::Rc;
 
-use super::{core::eval, val::Val};
-use crate::ast::Call;
+use super::core::{exec, exec_body};
+use super::env::Frame;
+use super::error::RuntimeError;
+use super::memo::MemoKey;
+use super::native::Native;
+use super::resolve::{Expr, Function};
+use super::runtime::Runtime;
+use super::val::Val;
+use crate::ast::Location;
 
-pub fn eval_call(call: Call, scope: &mut HashMap<String, Val>) -> Result<Val, Error> {
-    match eval(*call.callee, scope) {
-        Ok(Val::Closure { f, env }) => {
-            let mut new_scope = env.borrow_mut().clone();
-            for (param, arg) in f.parameters.into_iter().zip(call.arguments.clone()) {
-                new_scope.insert(param.text, eval(arg, scope)?);
+/// Outcome of preparing a call.
+pub enum Prepared {
+    /// The result was already known from the memoization cache.
+    Done(Val),
+    /// The body of the called function, the frame it must be evaluated in
+    /// and, for memoized calls, the key its result must be cached under.
+    Enter {
+        body: Rc<Expr>,
+        frame: Rc<Frame>,
+        key: Option<MemoKey>,
+    },
+}
+
+/// Evaluate the callee and the arguments of a call, returning the body of
+/// the called function together with the frame it must be evaluated in.
+/// Natives are called right away.
+///
+/// The body itself is not evaluated here so that `exec` can run it in
+/// place of the call, keeping tail calls off the native stack.
+pub fn prepare_call(
+    callee: &Rc<Expr>,
+    arguments: &[Rc<Expr>],
+    location: &Location,
+    frame: &Rc<Frame>,
+    rt: &mut Runtime,
+) -> Result<Prepared, RuntimeError> {
+    let callee = exec(callee, frame, rt)?;
+    match &callee {
+        Val::Closure { f, env } => {
+            if arguments.len() != f.arity {
+                return mismatch(&callee, f.arity, arguments, location, frame, rt);
+            }
+            let mut args = Vec::with_capacity(f.slots);
+            for arg in arguments {
+                args.push(exec(arg, frame, rt)?);
+            }
+            let key = rt.memo.key(f, env, &args);
+            if let Some(val) = key.as_ref().and_then(|key| rt.memo.get(key)) {
+                return Ok(Prepared::Done(val));
+            }
+            Ok(Prepared::Enter {
+                body: f.body.clone(),
+                frame: Frame::enter(env, args, f.slots),
+                key,
+            })
+        }
+        Val::Native(native) => {
+            if arguments.len() != native.arity {
+                return mismatch(&callee, native.arity, arguments, location, frame, rt);
             }
-            eval(*f.value, &mut new_scope)
+            let mut args = Vec::with_capacity(native.arity);
+            for arg in arguments {
+                args.push(exec(arg, frame, rt)?);
+            }
+            Ok(Prepared::Done(call_native(native, &args, location)?))
+        }
+        val => Err(RuntimeError::TypeMismatch {
+            expected: "closure",
+            actual: val.type_name(),
+            location: location.clone(),
+        }),
+    }
+}
+
+/// Handle a call of a function of `arity` parameters on another number of
+/// arguments: an error, unless partial application is enabled and some
+/// arguments are missing.
+fn mismatch(
+    callee: &Val,
+    arity: usize,
+    arguments: &[Rc<Expr>],
+    location: &Location,
+    frame: &Rc<Frame>,
+    rt: &mut Runtime,
+) -> Result<Prepared, RuntimeError> {
+    if rt.application == Application::Exact || arguments.len() > arity {
+        return Err(RuntimeError::Arity {
+            expected: arity,
+            actual: arguments.len(),
+            location: location.clone(),
+        });
+    }
+    let mut args = Vec::with_capacity(arguments.len());
+    for arg in arguments {
+        args.push(exec(arg, frame, rt)?);
+    }
+    Ok(Prepared::Done(partial(callee, arity, args, location)))
+}
+
+/// Closure taking the `arity - args.len()` missing arguments of `callee`
+/// and calling it on `args` followed by them. Its frame holds `callee` and
+/// `args`, the call is reported at `location`.
+fn partial(callee: &Val, arity: usize, args: Vec<Val>, location: &Location) -> Val {
+    let given = args.len();
+    let arguments = (1..=given)
+        .map(|slot| Expr::Var { depth: 1, slot })
+        .chain((0..arity - given).map(|slot| Expr::Var { depth: 0, slot }));
+    let f = Function {
+        name: None,
+        arity: arity - given,
+        slots: arity - given,
+        body: Rc::new(Expr::Call {
+            callee: Rc::new(Expr::Var { depth: 1, slot: 0 }),
+            arguments: arguments.map(Rc::new).collect(),
+            location: location.clone(),
+        }),
+    };
+    let mut slots = Vec::with_capacity(given + 1);
+    slots.push(callee.clone());
+    slots.extend(args);
+    Val::Closure {
+        f: Rc::new(f),
+        env: Frame::new(slots, None),
+    }
+}
+
+fn call_native(native: &Native, args: &[Val], location: &Location) -> Result<Val, RuntimeError> {
+    native.call(args).map_err(|message| RuntimeError::Native {
+        name: native.name.clone(),
+        message,
+        location: location.clone(),
+    })
+}
+
+/// What calls with fewer arguments than the called function has
+/// parameters do. Calls with more arguments always fail.
+/// ```rust
+/// use rinha_compiladores::core::eval_with;
+/// use rinha_compiladores::env::Env;
+/// use rinha_compiladores::eval_call::Application;
+/// use rinha_compiladores::runtime::Runtime;
+///
+/// let mut rt = Runtime::default();
+/// rt.application = Application::Partial;
+///
+/// let source = "let add = fn (a, b) => { a + b }; let inc = add(1); inc(41)";
+/// let file = rinha::parser::parse_or_report("example", source).expect("parse error");
+/// let result = eval_with(file.expression.into(), &Env::new(), &mut rt).expect("error");
+///
+/// assert_eq!(result.to_string(), "42");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Application {
+    /// Fail with [`RuntimeError::Arity`].
+    #[default]
+    Exact,
+    /// Return a closure taking the missing arguments.
+    Partial,
+}
+
+/// Call a function value on already evaluated arguments, e.g. a closure a
+/// program returned to Rust, as a new evaluation for the limits of `rt`.
+/// `location` is the one errors of the call itself are reported at.
+/// ```rust
+/// use rinha_compiladores::core::eval;
+/// use rinha_compiladores::env::Env;
+/// use rinha_compiladores::eval_call::apply;
+/// use rinha_compiladores::runtime::Runtime;
+/// use rinha_compiladores::val::Val;
+///
+/// let file = rinha::parser::parse_or_report("example", "fn (a, b) => { a * b }").expect("parse error");
+/// let mul = eval(file.expression.into(), &Env::new()).expect("error");
+/// let result = apply(&mul, vec![Val::Int(6), Val::Int(7)], &Default::default(), &mut Runtime::default());
+///
+/// assert_eq!(result.expect("error").to_string(), "42");
+/// ```
+pub fn apply(
+    callee: &Val,
+    args: Vec<Val>,
+    location: &Location,
+    rt: &mut Runtime,
+) -> Result<Val, RuntimeError> {
+    rt.start();
+    let arity = match callee {
+        Val::Closure { f, .. } => f.arity,
+        Val::Native(native) => native.arity,
+        val => {
+            return Err(RuntimeError::TypeMismatch {
+                expected: "closure",
+                actual: match val {
+                    Val::Lambda(_) => "closure of the VM",
+                    val => val.type_name(),
+                },
+                location: location.clone(),
+            })
         }
-        a => Err(Error::new(
-            std::io::ErrorKind::Other,
-            format!("Call: invalid type {:?}", a),
-        )),
+    };
+    if args.len() < arity && rt.application == Application::Partial {
+        return Ok(partial(callee, arity, args, location));
+    }
+    if args.len() != arity {
+        return Err(RuntimeError::Arity {
+            expected: arity,
+            actual: args.len(),
+            location: location.clone(),
+        });
+    }
+    match callee {
+        Val::Closure { f, env } => exec_body(&f.body, &Frame::enter(env, args, f.slots), rt),
+        Val::Native(native) => call_native(native, &args, location),
+        _ => unreachable!("not a function"),
     }
 }
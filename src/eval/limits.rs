@@ -0,0 +1,132 @@
+use std::fmt::{Display, Formatter};
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+use super::val::Val;
+
+/// Number of calls between two looks at the clock, which is slow compared
+/// to a call.
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// Depth of the default [`Limits`], which keeps a runaway recursion from
+/// using up the memory of the process while letting deep ones run.
+pub const DEFAULT_DEPTH: usize = 100_000;
+
+/// Resources an evaluation may use, unlimited when `None`. By default only
+/// the depth is limited, to [`DEFAULT_DEPTH`].
+///
+/// Only function calls are counted, which every long running program
+/// makes, so both backends count them alike.
+/// ```rust
+/// use rinha_compiladores::core::eval_with;
+/// use rinha_compiladores::env::Env;
+/// use rinha_compiladores::error::RuntimeError;
+/// use rinha_compiladores::limits::{Limit, Limits};
+/// use rinha_compiladores::runtime::Runtime;
+///
+/// let mut rt = Runtime::default();
+/// rt.limits = Limits { calls: Some(1000), ..Default::default() };
+///
+/// let file = rinha::parser::parse_or_report("example", "let f = fn () => { f() }; f()").expect("parse error");
+/// let error = eval_with(file.expression.into(), &Env::new(), &mut rt).unwrap_err();
+///
+/// assert!(matches!(error, RuntimeError::LimitExceeded { limit: Limit::Calls(1000), .. }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// Function calls made.
+    pub calls: Option<u64>,
+    /// Calls in progress at the same time, the program itself included. A
+    /// tail call replaces the call it is made from.
+    pub depth: Option<usize>,
+    /// Bytes of the strings built by `+` and of the tuples created, freed
+    /// or not.
+    pub memory: Option<usize>,
+    /// Time the evaluation may take.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            calls: None,
+            depth: Some(DEFAULT_DEPTH),
+            memory: None,
+            timeout: None,
+        }
+    }
+}
+
+/// A limit an evaluation exceeded, with its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Calls(u64),
+    Depth(usize),
+    Memory(usize),
+    Timeout(Duration),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Calls(calls) => write!(f, "limit of {calls} calls"),
+            Limit::Depth(depth) => write!(f, "call depth limit of {depth}"),
+            Limit::Memory(bytes) => write!(f, "memory limit of {bytes} bytes"),
+            Limit::Timeout(timeout) => write!(f, "time limit of {timeout:?}"),
+        }
+    }
+}
+
+/// Resources used by the evaluation in progress.
+#[derive(Debug, Clone, Default)]
+pub struct Usage {
+    pub calls: u64,
+    pub memory: usize,
+    /// Calls in progress, the program itself included.
+    pub depth: usize,
+    deadline: Option<Instant>,
+}
+
+impl Usage {
+    /// Start counting for a new evaluation.
+    pub fn start(&mut self, limits: &Limits) {
+        *self = Usage {
+            calls: 0,
+            memory: 0,
+            depth: 0,
+            deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+        };
+    }
+
+    /// Count a call, which makes `depth` calls in progress.
+    pub fn call(&mut self, limits: &Limits, depth: usize) -> Result<(), Limit> {
+        self.calls += 1;
+        if let Some(calls) = limits.calls.filter(|&calls| self.calls > calls) {
+            return Err(Limit::Calls(calls));
+        }
+        if let Some(max) = limits.depth.filter(|&max| depth > max) {
+            return Err(Limit::Depth(max));
+        }
+        if let (Some(deadline), Some(timeout)) = (self.deadline, limits.timeout) {
+            // `is_multiple_of` needs a newer toolchain than the Dockerfile's.
+            #[allow(clippy::manual_is_multiple_of)]
+            if self.calls % CLOCK_INTERVAL == 0 && Instant::now() > deadline {
+                return Err(Limit::Timeout(timeout));
+            }
+        }
+        Ok(())
+    }
+
+    /// Count the memory of a value just created.
+    pub fn allocate(&mut self, limits: &Limits, val: &Val) -> Result<(), Limit> {
+        self.memory += match val {
+            Val::Str(s) => s.len(),
+            Val::Tuple(_) => 2 * size_of::<Val>(),
+            _ => 0,
+        };
+        match limits.memory.filter(|&memory| self.memory > memory) {
+            Some(memory) => Err(Limit::Memory(memory)),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+use super::env::Frame;
+use super::resolve::{Expr, Function};
+use super::val::Val;
+use crate::ast::{BinaryOp, RED_ZONE, STACK_SIZE};
+
+/// Identifies a closure by the addresses of its function and of the
+/// frame it captured.
+type Closure = (usize, usize);
+
+/// Closures called by a function body, with whether the call is in tail
+/// position.
+type Calls = Vec<(Rc<Function>, Rc<Frame>, bool)>;
+
+fn identity(f: &Rc<Function>, env: &Rc<Frame>) -> Closure {
+    (Rc::as_ptr(f) as usize, Rc::as_ptr(env) as usize)
+}
+
+/// Identifies a call of a pure closure with a given list of arguments.
+///
+/// Arguments are compared as [`Val`]s, closures by identity.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct MemoKey {
+    closure: Closure,
+    args: Vec<Val>,
+}
+
+/// Cache of the results of calls to pure closures.
+///
+/// A closure is pure when its body cannot reach a `print`, either directly
+/// or through the closures it calls by name. Calls through parameters or
+/// through computed callees are assumed to print, so higher-order functions
+/// like `map` are never memoized.
+///
+/// Nor are loops, pure closures whose only recursive calls are tail calls
+/// to themselves: every iteration runs with new arguments, so caching them
+/// would keep one result per iteration for nothing.
+///
+/// Closures are identified by their function and captured frame,
+/// which the cache keeps alive so that their addresses cannot be reused by
+/// another closure.
+///
+/// The cache only lasts for one evaluation, [`Runtime::start`] clears it.
+/// A REPL session or an embedder running many programs would otherwise
+/// keep the results of all of them, and every frame they refer to.
+///
+/// [`Runtime::start`]: super::runtime::Runtime::start
+pub struct Memo {
+    pub enabled: bool,
+    /// Calls answered from the cache.
+    pub hits: usize,
+    /// Calls of pure closures that had to be evaluated.
+    pub misses: usize,
+    results: HashMap<MemoKey, Val>,
+    purity: HashMap<Closure, (Val, bool)>,
+    /// Pure closures that are loops, never memoized.
+    loops: HashSet<Closure>,
+}
+
+impl Default for Memo {
+    /// Memoization is on for release builds and off for debug builds, where
+    /// every call should really run.
+    fn default() -> Self {
+        Self::new(!cfg!(debug_assertions))
+    }
+}
+
+impl Memo {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            hits: 0,
+            misses: 0,
+            results: HashMap::new(),
+            purity: HashMap::new(),
+            loops: HashSet::new(),
+        }
+    }
+
+    /// Forget the cached results, the closures known to be pure or not and
+    /// the statistics.
+    pub fn clear(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+        self.results.clear();
+        self.purity.clear();
+        self.loops.clear();
+    }
+
+    /// Number of cached results.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Build the cache key of a call, or `None` when the call must not be
+    /// memoized.
+    pub fn key(&mut self, f: &Rc<Function>, env: &Rc<Frame>, args: &[Val]) -> Option<MemoKey> {
+        if !self.enabled {
+            return None;
+        }
+        // Hashing a tuple walks all of it, which costs more than most calls
+        // on lists save.
+        if args.iter().any(|arg| matches!(arg, Val::Tuple(_))) {
+            return None;
+        }
+        let closure = identity(f, env);
+        match self.is_pure(f, env) && !self.loops.contains(&closure) {
+            true => Some(MemoKey {
+                closure,
+                args: args.to_vec(),
+            }),
+            false => None,
+        }
+    }
+
+    /// Look a call up in the cache, keeping the hit statistics.
+    pub fn get(&mut self, key: &MemoKey) -> Option<Val> {
+        match self.results.get(key) {
+            Some(val) => {
+                self.hits += 1;
+                Some(val.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: MemoKey, val: Val) {
+        self.results.insert(key, val);
+    }
+
+    /// Decide whether a closure is pure, analysing at once every closure it
+    /// can reach so that recursive calls are handled.
+    fn is_pure(&mut self, f: &Rc<Function>, env: &Rc<Frame>) -> bool {
+        let root = identity(f, env);
+        if let Some((_, pure)) = self.purity.get(&root) {
+            return *pure;
+        }
+
+        // Closures whose purity is unknown, with whether their own body is
+        // pure and the closures they call.
+        let mut nodes: HashMap<Closure, (Val, bool, Vec<Closure>)> = HashMap::new();
+        let mut pending = vec![(f.clone(), env.clone())];
+        while let Some((f, env)) = pending.pop() {
+            let id = identity(&f, &env);
+            if nodes.contains_key(&id) || self.purity.contains_key(&id) {
+                continue;
+            }
+            let mut calls = Vec::new();
+            let pure = collect_calls(&f.body, &env, true, &mut calls);
+            let callees: Vec<_> = calls.iter().map(|(f, env, _)| identity(f, env)).collect();
+            let mut recursive = calls.iter().filter(|(f, env, _)| identity(f, env) == id);
+            if recursive.clone().next().is_some() && recursive.all(|(_, _, tail)| *tail) {
+                self.loops.insert(id);
+            }
+            nodes.insert(id, (Val::Closure { f, env }, pure, callees));
+            pending.extend(calls.into_iter().map(|(f, env, _)| (f, env)));
+        }
+
+        // A closure calling an impure one is impure as well.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let impure = nodes
+                .iter()
+                .filter(|(_, (_, pure, callees))| {
+                    *pure
+                        && callees.iter().any(|callee| match nodes.get(callee) {
+                            Some((_, pure, _)) => !pure,
+                            None => !self.purity[callee].1,
+                        })
+                })
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            for id in impure {
+                nodes.get_mut(&id).expect("unknown closure").1 = false;
+                changed = true;
+            }
+        }
+
+        for (id, (closure, pure, _)) in nodes {
+            self.purity.insert(id, (closure, pure));
+        }
+        self.purity[&root].1
+    }
+}
+
+/// Walk a function body, collecting the closures it calls by name with
+/// whether the call is in tail position. Returns `false` as soon as the body
+/// is found to be impure by itself.
+///
+/// Only callees captured from enclosing frames are followed: the slots of
+/// the function's own frame hold arguments and values computed by the call.
+fn collect_calls(expr: &Expr, env: &Rc<Frame>, tail: bool, calls: &mut Calls) -> bool {
+    // The walk recurses as deep as the body is nested.
+    stacker::maybe_grow(RED_ZONE, STACK_SIZE, || match expr {
+        Expr::Print { .. } => false,
+        Expr::Call {
+            callee, arguments, ..
+        } => {
+            let callee = match callee.as_ref() {
+                Expr::Var { depth, slot } if *depth > 0 => match env.get(depth - 1, *slot) {
+                    Val::Closure { f, env } => {
+                        calls.push((f, env, tail));
+                        true
+                    }
+                    _ => false,
+                },
+                _ => false,
+            };
+            callee
+                && arguments
+                    .iter()
+                    .all(|arg| collect_calls(arg, env, false, calls))
+        }
+        Expr::Let { value, next, .. } => {
+            collect_calls(value, env, false, calls) && collect_calls(next, env, tail, calls)
+        }
+        Expr::If {
+            condition,
+            then,
+            otherwise,
+            ..
+        } => {
+            collect_calls(condition, env, false, calls)
+                && collect_calls(then, env, tail, calls)
+                && collect_calls(otherwise, env, tail, calls)
+        }
+        // The right operand of `&&` and `||` is evaluated in tail position.
+        Expr::Binary { op, lhs, rhs, .. } => {
+            let rhs_tail = tail && matches!(op, BinaryOp::And | BinaryOp::Or);
+            collect_calls(lhs, env, false, calls) && collect_calls(rhs, env, rhs_tail, calls)
+        }
+        Expr::First { value, .. } | Expr::Second { value, .. } => {
+            collect_calls(value, env, false, calls)
+        }
+        Expr::Tuple { first, second, .. } => {
+            collect_calls(first, env, false, calls) && collect_calls(second, env, false, calls)
+        }
+        // Creating a closure does not run its body.
+        Expr::Function(_) => true,
+        Expr::Int(_) | Expr::Str(_) | Expr::Bool(_) | Expr::Var { .. } | Expr::Error { .. } => true,
+    })
+}
+
+impl Display for Memo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "memo: {} hits, {} misses, {} cached results",
+            self.hits,
+            self.misses,
+            self.results.len()
+        )
+    }
+}
@@ -1,4 +1,13 @@
+pub mod convert;
 pub mod core;
+pub mod env;
+pub mod error;
 pub mod eval_binary;
 pub mod eval_call;
+pub mod limits;
+pub mod memo;
+pub mod native;
+pub mod output;
+pub mod resolve;
+pub mod runtime;
 pub mod val;
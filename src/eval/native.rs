@@ -0,0 +1,43 @@
+use std::fmt::{Debug, Formatter};
+
+use super::val::Val;
+
+/// Body of a [`Native`]: takes the arguments of a call, returns its value or
+/// the message of the error it fails with.
+pub type NativeFn = dyn Fn(&[Val]) -> Result<Val, String>;
+
+/// Function implemented in Rust, which programs call like a closure.
+///
+/// Natives are not pure, calls to them are never memoized.
+pub struct Native {
+    pub name: String,
+    /// Number of arguments it takes, calls with another number fail as for
+    /// closures.
+    pub arity: usize,
+    body: Box<NativeFn>,
+}
+
+impl Native {
+    pub fn new(
+        name: impl Into<String>,
+        arity: usize,
+        body: impl Fn(&[Val]) -> Result<Val, String> + 'static,
+    ) -> Self {
+        Native {
+            name: name.into(),
+            arity,
+            body: Box::new(body),
+        }
+    }
+
+    /// Run the function on exactly `arity` arguments.
+    pub fn call(&self, args: &[Val]) -> Result<Val, String> {
+        (self.body)(args)
+    }
+}
+
+impl Debug for Native {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Native({}/{})", self.name, self.arity)
+    }
+}
@@ -0,0 +1,110 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::io::{self, BufWriter, Write};
+use std::rc::Rc;
+
+use super::val::Val;
+
+/// Where the values given to `print` go.
+///
+/// Outputs may buffer what they are given: evaluations flush them when they
+/// end, successfully or not.
+/// ```rust
+/// use rinha_compiladores::core::eval_with;
+/// use rinha_compiladores::env::Env;
+/// use rinha_compiladores::output::Buffer;
+/// use rinha_compiladores::runtime::Runtime;
+///
+/// let buffer = Buffer::default();
+/// let mut rt = Runtime::default();
+/// rt.output = Box::new(buffer.clone());
+///
+/// let file = rinha::parser::parse_or_report("example", "let _ = print(1); print((2, \"a\"))").expect("parse error");
+/// eval_with(file.expression.into(), &Env::new(), &mut rt).expect("error");
+///
+/// assert_eq!(buffer.contents(), "1\n(2, a)\n");
+/// ```
+pub trait Output {
+    fn print(&mut self, val: &Val) -> io::Result<()>;
+
+    /// Write out what is buffered.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The default output: stdout, written to in blocks rather than line by
+/// line.
+pub struct Stdout(BufWriter<io::Stdout>);
+
+impl Default for Stdout {
+    fn default() -> Self {
+        Stdout(BufWriter::new(io::stdout()))
+    }
+}
+
+impl Output for Stdout {
+    fn print(&mut self, val: &Val) -> io::Result<()> {
+        writeln!(self.0, "{val}")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Output kept in memory, one line per value. Clones share the text, so a
+/// clone can be read after the evaluation owning the other is done.
+#[derive(Debug, Clone, Default)]
+pub struct Buffer(Rc<RefCell<String>>);
+
+impl Buffer {
+    /// Everything printed so far.
+    pub fn contents(&self) -> String {
+        self.0.borrow().clone()
+    }
+
+    /// Take everything printed so far, leaving the buffer empty.
+    pub fn take(&self) -> String {
+        self.0.take()
+    }
+}
+
+impl Output for Buffer {
+    fn print(&mut self, val: &Val) -> io::Result<()> {
+        let mut text = self.0.borrow_mut();
+        text.push_str(&val.to_string());
+        text.push('\n');
+        Ok(())
+    }
+}
+
+/// Output to any writer, one line per value.
+#[derive(Debug)]
+pub struct Writer<W: Write>(pub W);
+
+impl<W: Write> Output for Writer<W> {
+    fn print(&mut self, val: &Val) -> io::Result<()> {
+        writeln!(self.0, "{val}")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Output calling a function with each printed value.
+pub struct Callback<F: FnMut(&Val)>(pub F);
+
+impl<F: FnMut(&Val)> Output for Callback<F> {
+    fn print(&mut self, val: &Val) -> io::Result<()> {
+        (self.0)(val);
+        Ok(())
+    }
+}
+
+impl<F: FnMut(&Val)> Debug for Callback<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Callback")
+    }
+}
@@ -0,0 +1,342 @@
+use std::rc::Rc;
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::ast::{BinaryOp, Element, Let, Location, Term, RED_ZONE, STACK_SIZE};
+
+/// Term whose variables were resolved to the slot they live in.
+///
+/// Children are reference counted so the evaluator can walk a function
+/// body without cloning it.
+#[derive(Debug)]
+pub enum Expr {
+    Int(i32),
+    Str(String),
+    Bool(bool),
+    /// Slot `slot` of the frame `depth` functions out from the current one.
+    Var {
+        depth: usize,
+        slot: usize,
+    },
+    /// Store `value` in a slot of the current frame, then evaluate `next`.
+    Let {
+        slot: usize,
+        value: Rc<Expr>,
+        next: Rc<Expr>,
+    },
+    /// `location` is the one of the condition, which must be a boolean.
+    If {
+        condition: Rc<Expr>,
+        then: Rc<Expr>,
+        otherwise: Rc<Expr>,
+        location: Location,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Rc<Expr>,
+        rhs: Rc<Expr>,
+        location: Location,
+    },
+    Call {
+        callee: Rc<Expr>,
+        arguments: Vec<Rc<Expr>>,
+        location: Location,
+    },
+    Function(Rc<Function>),
+    Print {
+        value: Rc<Expr>,
+        location: Location,
+    },
+    First {
+        value: Rc<Expr>,
+        location: Location,
+    },
+    Second {
+        value: Rc<Expr>,
+        location: Location,
+    },
+    /// `location` is the one of the tuple, for the memory it takes.
+    Tuple {
+        first: Rc<Expr>,
+        second: Rc<Expr>,
+        location: Location,
+    },
+    Error {
+        message: String,
+        location: Location,
+    },
+}
+
+impl Drop for Expr {
+    fn drop(&mut self) {
+        // Dropping recurses as deep as the expression is nested, so the
+        // children nothing else shares are taken out and dropped on a stack
+        // grown as needed.
+        let take = |expr: &mut Rc<Expr>| {
+            if let Some(expr) = Rc::get_mut(expr) {
+                drop(std::mem::replace(expr, Expr::Int(0)));
+            }
+        };
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || match self {
+            Expr::Let { value, next, .. } => {
+                take(value);
+                take(next);
+            }
+            Expr::If {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                take(condition);
+                take(then);
+                take(otherwise);
+            }
+            Expr::Binary { lhs, rhs, .. } => {
+                take(lhs);
+                take(rhs);
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                take(callee);
+                arguments.iter_mut().for_each(take);
+            }
+            Expr::Function(function) => {
+                if let Some(function) = Rc::get_mut(function) {
+                    take(&mut function.body);
+                }
+            }
+            Expr::Print { value, .. } | Expr::First { value, .. } | Expr::Second { value, .. } => {
+                take(value)
+            }
+            Expr::Tuple { first, second, .. } => {
+                take(first);
+                take(second);
+            }
+            Expr::Int(_) | Expr::Str(_) | Expr::Bool(_) | Expr::Var { .. } | Expr::Error { .. } => {
+            }
+        })
+    }
+}
+
+/// Resolved function. Each call gets a frame of `slots` values whose first
+/// `arity` slots hold the arguments and the rest the `let`s of the body.
+#[derive(Debug)]
+pub struct Function {
+    /// Name of the `let` binding the function was defined by, if any.
+    pub name: Option<String>,
+    pub arity: usize,
+    pub slots: usize,
+    pub body: Rc<Expr>,
+}
+
+/// A variable used where no binding of its name is visible.
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("unbound variable `{name}`")]
+pub struct UnboundVariable {
+    pub name: String,
+    #[label("not defined")]
+    pub location: Location,
+}
+
+/// Resolve a whole program into the body of a function without
+/// parameters, whose enclosing frame holds the `globals` in order.
+///
+/// Every unbound variable of the program is reported, not only the first.
+/// ```rust
+/// use rinha_compiladores::ast::Term;
+/// use rinha_compiladores::resolve::resolve;
+///
+/// let file = rinha::parser::parse_or_report("example", "let x = 1; x + y + z").expect("parse error");
+/// let errors = resolve(&Term::from(file.expression), &[]).unwrap_err();
+///
+/// let names: Vec<_> = errors.iter().map(|e| e.name.as_str()).collect();
+/// assert_eq!(names, ["y", "z"]);
+/// ```
+pub fn resolve(term: &Term, globals: &[String]) -> Result<Rc<Function>, Vec<UnboundVariable>> {
+    let mut resolver = Resolver {
+        scopes: vec![Scope::default(), Scope::default()],
+        errors: Vec::new(),
+    };
+    for name in globals {
+        resolver.scopes[0].declare(name);
+    }
+    let body = resolver.term(term);
+    let script = resolver.scopes.pop().expect("missing top-level scope");
+    match resolver.errors.is_empty() {
+        true => Ok(Rc::new(Function {
+            name: None,
+            arity: 0,
+            slots: script.slots,
+            body,
+        })),
+        false => Err(resolver.errors),
+    }
+}
+
+/// Names visible in a function being resolved.
+#[derive(Default)]
+struct Scope {
+    /// Bindings in scope with their slots, innermost last.
+    names: Vec<(String, usize)>,
+    slots: usize,
+}
+
+impl Scope {
+    fn declare(&mut self, name: &str) -> usize {
+        let slot = self.slots;
+        self.slots += 1;
+        self.names.push((name.to_string(), slot));
+        slot
+    }
+}
+
+struct Resolver {
+    /// Functions being resolved, the innermost last.
+    scopes: Vec<Scope>,
+    errors: Vec<UnboundVariable>,
+}
+
+impl Resolver {
+    fn current(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("no scope")
+    }
+
+    fn lookup(&self, name: &str) -> Option<Expr> {
+        self.scopes
+            .iter()
+            .rev()
+            .enumerate()
+            .find_map(|(depth, scope)| {
+                let (_, slot) = scope.names.iter().rev().find(|(n, _)| n == name)?;
+                Some(Expr::Var { depth, slot: *slot })
+            })
+    }
+
+    fn function(&mut self, f: &crate::ast::Function, name: Option<String>) -> Rc<Function> {
+        let mut scope = Scope::default();
+        for param in &f.parameters {
+            scope.declare(&param.text);
+        }
+        self.scopes.push(scope);
+        let body = self.term(&f.value);
+        let scope = self.scopes.pop().expect("missing function scope");
+        Rc::new(Function {
+            name,
+            arity: f.parameters.len(),
+            slots: scope.slots,
+            body,
+        })
+    }
+
+    /// Resolve `let`s defining functions that can all call each other, see
+    /// [`Let::group`]. Their slots are all filled before any of them can be
+    /// called.
+    fn group(&mut self, group: &[&Let]) -> Rc<Expr> {
+        let slots: Vec<_> = group
+            .iter()
+            .map(|l| self.current().declare(&l.name.text))
+            .collect();
+        let functions: Vec<_> = group
+            .iter()
+            .map(|l| {
+                let Term::Function(f) = l.value.as_ref() else {
+                    unreachable!("not a function");
+                };
+                self.function(f, Some(l.name.text.clone()))
+            })
+            .collect();
+        let last = group.last().expect("empty group");
+        let next = self.term(&last.next);
+        for _ in group {
+            self.current().names.pop();
+        }
+        let lets = slots.into_iter().zip(functions).rev();
+        lets.fold(next, |next, (slot, f)| {
+            Rc::new(Expr::Let {
+                slot,
+                value: Rc::new(Expr::Function(f)),
+                next,
+            })
+        })
+    }
+
+    fn term(&mut self, term: &Term) -> Rc<Expr> {
+        // Resolving recurses as deep as the program is nested.
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || {
+            let expr = match term {
+                Term::Int(number) => Expr::Int(number.value),
+                Term::Str(str) => Expr::Str(str.value.clone()),
+                Term::Bool(bool) => Expr::Bool(bool.value),
+                Term::Var(v) => match self.lookup(&v.text) {
+                    Some(var) => var,
+                    None => {
+                        self.errors.push(UnboundVariable {
+                            name: v.text.clone(),
+                            location: v.location.clone(),
+                        });
+                        Expr::Error {
+                            message: format!("unbound variable `{}`", v.text),
+                            location: v.location.clone(),
+                        }
+                    }
+                },
+                Term::Let(l) => {
+                    let group = l.group(|name| self.lookup(name).is_some());
+                    if !group.is_empty() {
+                        return self.group(&group);
+                    }
+                    // Values other than functions only see the bindings that
+                    // were already there.
+                    let value = self.term(&l.value);
+                    let slot = self.current().declare(&l.name.text);
+                    let next = self.term(&l.next);
+                    self.current().names.pop();
+                    Expr::Let { slot, value, next }
+                }
+                Term::If(i) => Expr::If {
+                    condition: self.term(&i.condition),
+                    then: self.term(&i.then),
+                    otherwise: self.term(&i.otherwise),
+                    location: i.condition.location().clone(),
+                },
+                Term::Binary(bin) => Expr::Binary {
+                    op: bin.op,
+                    lhs: self.term(&bin.lhs),
+                    rhs: self.term(&bin.rhs),
+                    location: bin.location.clone(),
+                },
+                Term::Call(call) => Expr::Call {
+                    callee: self.term(&call.callee),
+                    arguments: call.arguments.iter().map(|arg| self.term(arg)).collect(),
+                    location: call.location.clone(),
+                },
+                Term::Function(f) => Expr::Function(self.function(f, None)),
+                Term::Print(print) => Expr::Print {
+                    value: self.term(&print.value),
+                    location: print.location.clone(),
+                },
+                Term::First(f) => Expr::First {
+                    value: self.term(&f.value),
+                    location: f.location.clone(),
+                },
+                Term::Second(s) => Expr::Second {
+                    value: self.term(&s.value),
+                    location: s.location.clone(),
+                },
+                Term::Tuple(t) => Expr::Tuple {
+                    first: self.term(&t.first),
+                    second: self.term(&t.second),
+                    location: t.location.clone(),
+                },
+                Term::Error(e) => Expr::Error {
+                    message: e.message.clone(),
+                    location: e.location.clone(),
+                },
+            };
+            Rc::new(expr)
+        })
+    }
+}
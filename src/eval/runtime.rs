@@ -0,0 +1,129 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::error::RuntimeError;
+use super::eval_binary::Arithmetic;
+use super::eval_call::Application;
+use super::limits::{Limits, Usage};
+use super::memo::Memo;
+use super::output::{Output, Stdout};
+use super::val::Val;
+use crate::ast::Location;
+
+/// State shared by all the steps of an evaluation.
+pub struct Runtime {
+    /// Results of calls to pure closures.
+    pub memo: Memo,
+    /// Stops the evaluation when set, from another thread or a signal
+    /// handler.
+    pub interrupt: Interrupt,
+    /// Where `print` writes to, buffered stdout by default.
+    pub output: Box<dyn Output>,
+    pub limits: Limits,
+    pub arithmetic: Arithmetic,
+    pub application: Application,
+    /// Resources used by the last evaluation, counted against `limits`.
+    pub usage: Usage,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Runtime {
+            memo: Memo::default(),
+            interrupt: Interrupt::default(),
+            output: Box::new(Stdout::default()),
+            limits: Limits::default(),
+            arithmetic: Arithmetic::default(),
+            application: Application::default(),
+            usage: Usage::default(),
+        }
+    }
+}
+
+impl Runtime {
+    /// Start counting the resources of a new evaluation, with an empty
+    /// memoization cache.
+    pub fn start(&mut self) {
+        self.usage.start(&self.limits);
+        self.memo.clear();
+    }
+
+    /// Enter a call, nested in the ones in progress.
+    pub fn enter(&mut self) {
+        self.usage.depth += 1;
+    }
+
+    pub fn leave(&mut self) {
+        self.usage.depth -= 1;
+    }
+
+    /// Check that a call at `location` may run: the evaluation was not
+    /// interrupted and stays within its limits. A `nested` call is made
+    /// while the current one is in progress, a tail call replaces it.
+    pub fn call(&mut self, location: &Location, nested: bool) -> Result<(), RuntimeError> {
+        if self.interrupt.is_interrupted() {
+            return Err(RuntimeError::Interrupted {
+                location: location.clone(),
+            });
+        }
+        self.usage
+            .call(&self.limits, self.usage.depth + nested as usize)
+            .map_err(|limit| RuntimeError::LimitExceeded {
+                limit,
+                location: location.clone(),
+            })
+    }
+
+    /// Count a string or a tuple created at `location` against the memory
+    /// limit.
+    pub fn allocate(&mut self, val: &Val, location: &Location) -> Result<(), RuntimeError> {
+        self.usage
+            .allocate(&self.limits, val)
+            .map_err(|limit| RuntimeError::LimitExceeded {
+                limit,
+                location: location.clone(),
+            })
+    }
+}
+
+/// Flag to cancel an evaluation from the outside. Clones share the flag.
+///
+/// The evaluation checks it at every call, which every long running
+/// program makes, and fails with [`RuntimeError::Interrupted`] once it is
+/// set. The flag stays set until [`Interrupt::reset`].
+/// ```rust
+/// use rinha_compiladores::core::eval_with;
+/// use rinha_compiladores::env::Env;
+/// use rinha_compiladores::error::RuntimeError;
+/// use rinha_compiladores::runtime::Runtime;
+///
+/// let mut rt = Runtime::default();
+/// let interrupt = rt.interrupt.clone();
+/// std::thread::spawn(move || interrupt.interrupt());
+///
+/// let source = "let forever = fn (n) => { forever(n + 1) }; forever(0)";
+/// let file = rinha::parser::parse_or_report("example", source).expect("parse error");
+/// let error = eval_with(file.expression.into(), &Env::new(), &mut rt).unwrap_err();
+///
+/// assert!(matches!(error, RuntimeError::Interrupted { .. }));
+/// ```
+///
+/// [`RuntimeError::Interrupted`]: super::error::RuntimeError::Interrupted
+#[derive(Debug, Clone, Default)]
+pub struct Interrupt(Arc<AtomicBool>);
+
+impl Interrupt {
+    /// Ask the evaluations using this flag to stop.
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Let evaluations run again.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
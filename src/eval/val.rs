@@ -1,31 +1,216 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter, Result};
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
-use crate::ast::Function;
+use super::env::Frame;
+use super::native::Native;
+use super::resolve::Function;
+use crate::compiler::bytecode::Lambda;
 
+/// Pair of values. Lists are chains of tuples in their second elements, so
+/// tuples are compared, hashed, written and dropped walking the second
+/// elements in a loop: recursing would overflow the stack on long lists.
 #[derive(Debug, Clone)]
 pub struct Tuple {
-    pub f: Box<Val>,
-    pub s: Box<Val>,
+    pub f: Rc<Val>,
+    pub s: Rc<Val>,
 }
 
+/// A value of a program.
+///
+/// Values are equal, hashed and ordered by their contents, tuples element
+/// by element, and closures by their identity: a closure is only equal to
+/// itself. Values of different types are ordered by type. This makes them
+/// usable as map keys; programs compare values with [`compare`] instead.
+///
+/// [`compare`]: crate::eval::eval_binary::compare
+/// ```rust
+/// use std::collections::HashSet;
+/// use rinha_compiladores::convert::IntoVal;
+///
+/// let values: HashSet<_> = [(1, "a").into_val(), (1, "a").into_val(), 2.into_val()].into();
+///
+/// assert_eq!(values.len(), 2);
+/// assert!(1.into_val() < (0, 0).into_val());
+/// ```
 #[derive(Debug, Clone)]
 pub enum Val {
     Int(i32),
     Bool(bool),
     Str(String),
     Closure {
-        f: Function,
-        env: Rc<RefCell<HashMap<String, Val>>>,
+        f: Rc<Function>,
+        env: Rc<Frame>,
     },
     Tuple(Tuple),
+    /// Closure created by the bytecode VM.
+    Lambda(Rc<Lambda>),
+    /// Function of the host, see [`Native`].
+    Native(Rc<Native>),
+}
+
+impl Val {
+    /// Name of the type of the value, as shown in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Val::Int(_) => "int",
+            Val::Bool(_) => "bool",
+            Val::Str(_) => "str",
+            Val::Closure { .. } | Val::Lambda(_) | Val::Native(_) => "closure",
+            Val::Tuple(_) => "tuple",
+        }
+    }
+
+    /// Position of the type of the value in the order of values of
+    /// different types.
+    fn rank(&self) -> u8 {
+        match self {
+            Val::Int(_) => 0,
+            Val::Bool(_) => 1,
+            Val::Str(_) => 2,
+            Val::Tuple(_) => 3,
+            Val::Closure { .. } => 4,
+            Val::Lambda(_) => 5,
+            Val::Native(_) => 6,
+        }
+    }
+
+    /// Addresses identifying a closure, zero for other values.
+    fn identity(&self) -> (usize, usize) {
+        match self {
+            Val::Closure { f, env } => (Rc::as_ptr(f) as usize, Rc::as_ptr(env) as usize),
+            Val::Lambda(lambda) => (Rc::as_ptr(lambda) as usize, 0),
+            Val::Native(native) => (Rc::as_ptr(native) as usize, 0),
+            _ => (0, 0),
+        }
+    }
+}
+
+impl PartialEq for Val {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Val {}
+
+impl PartialOrd for Val {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Val {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Val::Int(a), Val::Int(b)) => a.cmp(b),
+            (Val::Bool(a), Val::Bool(b)) => a.cmp(b),
+            (Val::Str(a), Val::Str(b)) => a.cmp(b),
+            (Val::Tuple(a), Val::Tuple(b)) => a.cmp(b),
+            (a, b) => (a.rank(), a.identity()).cmp(&(b.rank(), b.identity())),
+        }
+    }
+}
+
+impl Hash for Val {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Val::Int(n) => n.hash(state),
+            Val::Bool(b) => b.hash(state),
+            Val::Str(s) => s.hash(state),
+            Val::Tuple(t) => t.hash(state),
+            closure => closure.identity().hash(state),
+        }
+    }
+}
+
+impl PartialEq for Tuple {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Tuple {}
+
+impl PartialOrd for Tuple {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Tuple {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (mut a, mut b) = (self, other);
+        loop {
+            match a.f.cmp(&b.f) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+            match (a.s.as_ref(), b.s.as_ref()) {
+                (Val::Tuple(s), Val::Tuple(t)) => (a, b) = (s, t),
+                (s, t) => return s.cmp(t),
+            }
+        }
+    }
+}
+
+impl Hash for Tuple {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut tuple = self;
+        loop {
+            tuple.f.hash(state);
+            match tuple.s.as_ref() {
+                Val::Tuple(next) => {
+                    tuple.s.rank().hash(state);
+                    tuple = next;
+                }
+                s => return s.hash(state),
+            }
+        }
+    }
+}
+
+impl Drop for Tuple {
+    fn drop(&mut self) {
+        // Unlink the tuples only this one refers to, one by one.
+        let Some(mut rest) = take_rest(&mut self.s) else {
+            return;
+        };
+        while let Some(next) = take_rest(&mut rest) {
+            rest = next;
+        }
+    }
+}
+
+thread_local! {
+    /// Value left in place of the rest of a list being dropped.
+    static UNIT: Rc<Val> = Rc::new(Val::Int(0));
+}
+
+/// Take the second element of the tuple `val` is, when nothing else refers
+/// to it.
+fn take_rest(val: &mut Rc<Val>) -> Option<Rc<Val>> {
+    match Rc::get_mut(val) {
+        Some(Val::Tuple(tuple)) => {
+            let unit = UNIT.try_with(Rc::clone).ok()?;
+            Some(std::mem::replace(&mut tuple.s, unit))
+        }
+        _ => None,
+    }
 }
 
 impl Display for Tuple {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "({}, {})", self.f, self.s)
+        let mut tuple = self;
+        let mut depth = 1;
+        write!(f, "({}, ", tuple.f)?;
+        while let Val::Tuple(next) = tuple.s.as_ref() {
+            write!(f, "({}, ", next.f)?;
+            (tuple, depth) = (next, depth + 1);
+        }
+        write!(f, "{}{}", tuple.s, ")".repeat(depth))
     }
 }
 
@@ -35,7 +220,7 @@ impl Display for Val {
             Val::Int(n) => write!(f, "{}", n),
             Val::Bool(b) => write!(f, "{}", b),
             Val::Str(s) => write!(f, "{}", s),
-            Val::Closure { .. } => write!(f, "<#closure>"),
+            Val::Closure { .. } | Val::Lambda(_) | Val::Native(_) => write!(f, "<#closure>"),
             Val::Tuple(t) => write!(f, "{}", t),
         }
     }
@@ -1,6 +1,9 @@
 pub mod ast;
+pub mod check;
 mod compiler;
 mod eval;
+pub mod pretty;
+pub mod repl;
 
 pub use ast::*;
 pub use compiler::*;
@@ -1,16 +1,31 @@
 #![recursion_limit = "256"]
 
-use ast::File;
-use chrono::Local;
-use serde::Deserialize;
+use clap_builder::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use miette::{miette, NamedSource, Report};
+use rinha_compiladores::ast::File;
+use rinha_compiladores::check::check;
+use rinha_compiladores::env::Env;
+use rinha_compiladores::eval_binary::Arithmetic;
+use rinha_compiladores::eval_call::Application;
+use rinha_compiladores::limits::Limits;
+use rinha_compiladores::pretty::{has_comments, print_file};
+use rinha_compiladores::runtime::Runtime;
+use rinha_compiladores::vm::Vm;
+use rinha_compiladores::{codegen, core, repl};
 
-use std::collections::HashMap;
-use std::env::args;
 use std::fs;
+use std::io::{stdin, Read};
+use std::process::exit;
+use std::time::{Duration, Instant};
 
-mod ast;
-mod compiler;
-mod eval;
+/// Exit code of programs that fail to parse, type check or run, and of
+/// unformatted files.
+const EXIT_FAILURE: i32 = 1;
+/// Exit code of unreadable or unwritable files, like clap's usage errors.
+const EXIT_USAGE: i32 = 2;
+
+/// Number of type errors shown by `check`.
+const MAX_TYPE_ERRORS: usize = 10;
 
 fn strip_bom(s: &str) -> &str {
     if s.as_bytes().get(0..3) == Some(&[0xEF, 0xBB, 0xBF]) {
@@ -20,35 +35,330 @@ fn strip_bom(s: &str) -> &str {
     }
 }
 
-fn main() {
-    let time_init = Local::now();
-    let program = match args().nth(1) {
-        Some(f) => {
-            let file = fs::read_to_string(&f).expect("File not found or file not decoded");
-            match f.ends_with(".json") {
-                true => {
-                    let mut dsz = serde_json::Deserializer::from_str(&file);
-                    dsz.disable_recursion_limit();
-                    let dsz = serde_stacker::Deserializer::new(&mut dsz);
-                    File::deserialize(dsz).expect("parse error")
-                }
-                false => File::from(
-                    rinha::parser::parse_or_report(&f, strip_bom(&file)).expect("parse error"),
+fn flag(name: &'static str, help: &'static str) -> Arg {
+    Arg::new(name)
+        .long(name)
+        .action(ArgAction::SetTrue)
+        .help(help)
+}
+
+fn input_args() -> [Arg; 2] {
+    [
+        Arg::new("file")
+            .value_name("FILE")
+            .help("Program to read, from stdin when missing or `-`"),
+        Arg::new("format")
+            .long("format")
+            .value_parser(["rinha", "json"])
+            .help("Format of the program [default: json for .json files, rinha otherwise]"),
+    ]
+}
+
+fn run_args() -> Vec<Arg> {
+    let mut args = input_args().to_vec();
+    args.extend([
+        flag("vm", "Run on the bytecode VM instead of the tree walker"),
+        // Only the tree walker memoizes calls.
+        flag(
+            "memo",
+            "Memoize calls to pure functions, even in debug builds",
+        )
+        .conflicts_with("vm"),
+        flag("no-memo", "Never memoize calls").conflicts_with("memo"),
+        flag("memo-stats", "Print the memoization cache usage on stderr").conflicts_with("vm"),
+        flag("check", "Type check the program before running it"),
+        flag("time", "Print the execution time on stderr"),
+        flag(
+            "wrapping",
+            "Let integer overflows wrap around instead of failing",
+        ),
+        flag(
+            "curry",
+            "Let calls missing arguments return a closure taking the rest",
+        ),
+        limit("max-calls", "N", "Stop the program after N function calls"),
+        limit(
+            "max-depth",
+            "N",
+            "Stop the program past N nested calls [default: 100000]",
+        ),
+        limit(
+            "max-memory",
+            "BYTES",
+            "Stop the program once it created BYTES of strings and tuples",
+        ),
+        Arg::new("timeout")
+            .long("timeout")
+            .value_name("SECONDS")
+            .value_parser(value_parser!(f64))
+            .help("Stop the program after SECONDS"),
+    ]);
+    args
+}
+
+fn limit(name: &'static str, value: &'static str, help: &'static str) -> Arg {
+    Arg::new(name)
+        .long(name)
+        .value_name(value)
+        .value_parser(value_parser!(u64))
+        .help(help)
+}
+
+fn limits(matches: &ArgMatches) -> Limits {
+    let get = |name| matches.get_one::<u64>(name).copied();
+    Limits {
+        calls: get("max-calls"),
+        depth: get("max-depth")
+            .map(|depth| depth as usize)
+            .or(Limits::default().depth),
+        memory: get("max-memory").map(|bytes| bytes as usize),
+        timeout: matches
+            .get_one::<f64>("timeout")
+            .map(|&seconds| Duration::from_secs_f64(seconds)),
+    }
+}
+
+fn cli() -> Command {
+    Command::new("rinha")
+        .about("Interpreter of the rinha language")
+        // `rinha FILE` runs the file, which is how the challenge runs us.
+        .args(run_args())
+        .args_conflicts_with_subcommands(true)
+        .subcommand(Command::new("run").about("Run a program").args(run_args()))
+        .subcommand(
+            Command::new("check")
+                .about("Type check a program and print its type")
+                .args(input_args()),
+        )
+        .subcommand(
+            Command::new("fmt")
+                .about("Format programs in place, or stdin to stdout")
+                .arg(
+                    Arg::new("files")
+                        .value_name("FILES")
+                        .num_args(0..)
+                        .help("Programs to format, stdin when missing or `-`"),
+                )
+                .arg(flag("check", "Fail on unformatted programs instead")),
+        )
+        .subcommand(
+            Command::new("emit-ast")
+                .about("Print the JSON AST of a program, as the official parser does")
+                .args(input_args())
+                .arg(flag("pretty", "Indent the JSON"))
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write the JSON to FILE instead of stdout"),
                 ),
+        )
+        .subcommand(Command::new("repl").about("Start an interactive session"))
+}
+
+fn fail(code: i32, report: Report) -> ! {
+    eprintln!("{report:?}");
+    exit(code)
+}
+
+/// A program read from a file or stdin.
+struct Input {
+    name: String,
+    text: String,
+    json: bool,
+}
+
+impl Input {
+    fn read(path: &str, format: Option<&str>) -> Input {
+        let (name, text) = match path {
+            "-" => {
+                let mut text = String::new();
+                if let Err(e) = stdin().read_to_string(&mut text) {
+                    fail(EXIT_USAGE, miette!("could not read stdin: {e}"));
+                }
+                ("<stdin>".to_string(), text)
+            }
+            path => match fs::read_to_string(path) {
+                Ok(text) => (path.to_string(), text),
+                Err(e) => fail(EXIT_USAGE, miette!("could not read {path}: {e}")),
+            },
+        };
+        let json = match format {
+            Some(format) => format == "json",
+            None => name.ends_with(".json"),
+        };
+        Input { name, text, json }
+    }
+
+    fn from_matches(matches: &ArgMatches) -> Input {
+        Input::read(
+            matches
+                .get_one::<String>("file")
+                .map_or("-", String::as_str),
+            matches.get_one::<String>("format").map(String::as_str),
+        )
+    }
+
+    fn source(&self) -> &str {
+        strip_bom(&self.text)
+    }
+
+    fn parse(&self) -> File {
+        match self.json {
+            true => File::from_json(&self.text).unwrap_or_else(|e| {
+                fail(EXIT_FAILURE, miette!("invalid AST in {}: {e}", self.name))
+            }),
+            false => match rinha::parser::parse_or_report(&self.name, self.source()) {
+                Ok(file) => File::from(file),
+                Err(e) => fail(EXIT_FAILURE, Report::new(e)),
+            },
+        }
+    }
+
+    /// Attach the source to a diagnostic. Locations point into the parsed
+    /// source, which a JSON AST does not come with.
+    fn report(&self, report: Report) -> Report {
+        match self.json {
+            true => report,
+            false => {
+                report.with_source_code(NamedSource::new(&self.name, self.source().to_string()))
+            }
+        }
+    }
+}
+
+/// Type check a program, exiting with its errors if it has any.
+fn check_program(input: &Input, program: &File) -> String {
+    match check(&program.expression) {
+        Ok(ty) => ty.to_string(),
+        Err(errors) => {
+            // Later errors are often consequences of the first ones.
+            for error in errors.iter().take(MAX_TYPE_ERRORS) {
+                eprintln!("{:?}", input.report(Report::new(error.clone())));
             }
+            if errors.len() > MAX_TYPE_ERRORS {
+                eprintln!(
+                    "... and {} more type errors",
+                    errors.len() - MAX_TYPE_ERRORS
+                );
+            }
+            exit(EXIT_FAILURE)
         }
-        None => panic!("No file specified"),
+    }
+}
+
+fn run(matches: &ArgMatches) {
+    let time_init = Instant::now();
+    let input = Input::from_matches(matches);
+    let program = input.parse();
+    if matches.get_flag("check") {
+        check_program(&input, &program);
+    }
+
+    let arithmetic = match matches.get_flag("wrapping") {
+        true => Arithmetic::Wrapping,
+        false => Arithmetic::Checked,
     };
+    let application = match matches.get_flag("curry") {
+        true => Application::Partial,
+        false => Application::Exact,
+    };
+    let mut rt = Runtime {
+        limits: limits(matches),
+        arithmetic,
+        application,
+        ..Default::default()
+    };
+    if matches.get_flag("memo") {
+        rt.memo.enabled = true;
+    }
+    if matches.get_flag("no-memo") {
+        rt.memo.enabled = false;
+    }
+    let result = match matches.get_flag("vm") {
+        true => Vm::default()
+            .with_limits(rt.limits.clone())
+            .with_arithmetic(arithmetic)
+            .with_application(application)
+            .run(codegen::compile(&program.expression)),
+        false => core::eval_with(program.expression, &Env::new(), &mut rt),
+    };
+    if matches.get_flag("memo-stats") {
+        eprintln!("{}", rt.memo);
+    }
+    if matches.get_flag("time") {
+        let time_end = time_init.elapsed();
+        eprintln!(
+            "Execution Time: {}s:{}ms",
+            time_end.as_secs(),
+            time_end.subsec_millis()
+        );
+    }
+    if let Err(e) = result {
+        fail(EXIT_FAILURE, input.report(Report::new(e)));
+    }
+}
 
-    match eval::core::eval(program.expression, &mut HashMap::new()) {
-        Ok(_) => {
-            let time_end = Local::now() - time_init;
-            println!(
-                "\n\nExecution Time: {}s:{}ms",
-                time_end.num_seconds(),
-                time_end.num_milliseconds() - (time_end.num_seconds() * 1000)
-            );
+fn fmt(matches: &ArgMatches) {
+    let check = matches.get_flag("check");
+    let paths: Vec<&str> = match matches.get_many::<String>("files") {
+        Some(files) => files.map(String::as_str).collect(),
+        None => vec!["-"],
+    };
+    let mut failed = false;
+    for path in paths {
+        let input = Input::read(path, None);
+        let formatted = print_file(&input.parse());
+        if check {
+            if formatted != input.source() {
+                eprintln!("{} is not formatted", input.name);
+                failed = true;
+            }
+        } else if path == "-" || input.json {
+            print!("{formatted}");
+        } else if has_comments(&input.text) {
+            eprintln!("{path} has comments, which formatting would remove");
+            failed = true;
+        } else if formatted != input.text {
+            if let Err(e) = fs::write(path, formatted) {
+                fail(EXIT_USAGE, miette!("could not write {path}: {e}"));
+            }
         }
-        Err(e) => println!("{:?}", e),
+    }
+    if failed {
+        exit(EXIT_FAILURE);
+    }
+}
+
+fn emit_ast(matches: &ArgMatches) {
+    let program = Input::from_matches(matches).parse();
+    let mut json = match program.to_json(matches.get_flag("pretty")) {
+        Ok(json) => json,
+        Err(e) => fail(EXIT_FAILURE, miette!("{e}")),
     };
+    json.push('\n');
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            if let Err(e) = fs::write(path, json) {
+                fail(EXIT_USAGE, miette!("could not write {path}: {e}"));
+            }
+        }
+        None => print!("{json}"),
+    }
+}
+
+fn main() {
+    let matches = cli().get_matches();
+    match matches.subcommand() {
+        Some(("run", matches)) => run(matches),
+        Some(("check", matches)) => {
+            let input = Input::from_matches(matches);
+            println!("{}", check_program(&input, &input.parse()));
+        }
+        Some(("fmt", matches)) => fmt(matches),
+        Some(("emit-ast", matches)) => emit_ast(matches),
+        Some(("repl", _)) => repl::run(),
+        _ => run(&matches),
+    }
 }
@@ -0,0 +1,261 @@
+use crate::ast::{BinaryOp, File, Term};
+
+const INDENT: &str = "  ";
+
+/// Precedence of terms that can be operands or callees without parentheses:
+/// literals, variables and calls.
+const ATOM: u8 = 4;
+
+/// Render a file as canonical rinha source.
+///
+/// Comments are not part of the AST, so they are lost.
+/// ```rust
+/// use rinha_compiladores::ast::File;
+/// use rinha_compiladores::pretty::print_file;
+///
+/// let source = "let add = fn (a, b) => a + b; print(add(1, 2))";
+/// let file = File::from(rinha::parser::parse_or_report("example", source).expect("parse error"));
+///
+/// assert_eq!(print_file(&file), "let add = fn (a, b) => { a + b };\nprint(add(1, 2))\n");
+/// ```
+pub fn print_file(file: &File) -> String {
+    let mut source = print_term(&file.expression);
+    source.push('\n');
+    source
+}
+
+/// Render a term as rinha source, with the parentheses its structure needs
+/// and no more.
+pub fn print_term(term: &Term) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        indent: 0,
+    };
+    printer.term(term);
+    printer.out
+}
+
+/// Whether rinha source has comments, which formatting would remove.
+pub fn has_comments(source: &str) -> bool {
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // Strings end at the next quote that is not escaped.
+            '"' => {
+                while let Some(c) = chars.next().filter(|&c| c != '"') {
+                    if c == '\\' {
+                        chars.next();
+                    }
+                }
+            }
+            '/' if matches!(chars.peek(), Some('/' | '*')) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn precedence(op: BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 3,
+        BinaryOp::Add | BinaryOp::Sub => 2,
+        BinaryOp::Eq
+        | BinaryOp::Neq
+        | BinaryOp::Lt
+        | BinaryOp::Gt
+        | BinaryOp::Lte
+        | BinaryOp::Gte
+        | BinaryOp::And
+        | BinaryOp::Or => 1,
+    }
+}
+
+fn symbol(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Rem => "%",
+        BinaryOp::Eq => "==",
+        BinaryOp::Neq => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Gt => ">",
+        BinaryOp::Lte => "<=",
+        BinaryOp::Gte => ">=",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+    }
+}
+
+/// Precedence of a term as an operand. Terms below every operator, like
+/// `if` or tuples, must be parenthesized to be used as one.
+fn level(term: &Term) -> u8 {
+    match term {
+        Term::Binary(bin) => precedence(bin.op),
+        Term::Int(_)
+        | Term::Str(_)
+        | Term::Bool(_)
+        | Term::Var(_)
+        | Term::Call(_)
+        | Term::Print(_)
+        | Term::First(_)
+        | Term::Second(_) => ATOM,
+        Term::Let(_) | Term::If(_) | Term::Function(_) | Term::Tuple(_) | Term::Error(_) => 0,
+    }
+}
+
+struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    /// Render a term between braces, on its own indented lines.
+    fn block(&mut self, term: &Term) {
+        self.out.push('{');
+        self.indent += 1;
+        self.newline();
+        self.term(term);
+        self.indent -= 1;
+        self.newline();
+        self.out.push('}');
+    }
+
+    /// Render a term where a `let` chain can appear as is: a whole file or
+    /// the body of a block.
+    fn term(&mut self, term: &Term) {
+        match term {
+            Term::Let(l) => {
+                self.out.push_str("let ");
+                self.out.push_str(&l.name.text);
+                self.out.push_str(" = ");
+                self.expr(&l.value);
+                self.out.push(';');
+                self.newline();
+                self.term(&l.next);
+            }
+            term => self.expr(term),
+        }
+    }
+
+    fn expr(&mut self, term: &Term) {
+        match term {
+            Term::Int(int) if int.value == i32::MIN => {
+                // There are no negative literals, and 2147483648 is not an i32.
+                self.out.push_str("((0 - 2147483647) - 1)");
+            }
+            Term::Int(int) if int.value < 0 => {
+                self.out.push_str(&format!("(0 - {})", -int.value));
+            }
+            Term::Int(int) => self.out.push_str(&int.value.to_string()),
+            Term::Str(str) => {
+                self.out.push('"');
+                self.out.push_str(&str.value);
+                self.out.push('"');
+            }
+            Term::Bool(bool) => self.out.push_str(&bool.value.to_string()),
+            Term::Var(v) => self.out.push_str(&v.text),
+            Term::Let(_) => self.block(term),
+            Term::If(i) => {
+                self.out.push_str("if (");
+                self.expr(&i.condition);
+                self.out.push_str(") ");
+                self.block(&i.then);
+                self.out.push_str(" else ");
+                self.block(&i.otherwise);
+            }
+            Term::Function(f) => {
+                let parameters: Vec<_> = f.parameters.iter().map(|p| p.text.as_str()).collect();
+                self.out.push_str("fn (");
+                self.out.push_str(&parameters.join(", "));
+                self.out.push_str(") => ");
+                if let Term::Let(_) | Term::If(_) = f.value.as_ref() {
+                    return self.block(&f.value);
+                }
+                // Short bodies stay on the line of the `fn`. The body is
+                // rendered once, at the indentation of a block, so that it
+                // can be moved into one when it spans lines.
+                let mut body = Printer {
+                    out: String::new(),
+                    indent: self.indent + 1,
+                };
+                body.expr(&f.value);
+                match body.out.contains('\n') {
+                    true => {
+                        self.out.push('{');
+                        self.indent += 1;
+                        self.newline();
+                        self.out.push_str(&body.out);
+                        self.indent -= 1;
+                        self.newline();
+                        self.out.push('}');
+                    }
+                    false => {
+                        self.out.push_str("{ ");
+                        self.out.push_str(&body.out);
+                        self.out.push_str(" }");
+                    }
+                }
+            }
+            Term::Binary(bin) => {
+                let precedence = precedence(bin.op);
+                // Operators are right associative.
+                self.operand(&bin.lhs, precedence + 1);
+                self.out.push(' ');
+                self.out.push_str(symbol(bin.op));
+                self.out.push(' ');
+                self.operand(&bin.rhs, precedence);
+            }
+            Term::Call(call) => {
+                self.operand(&call.callee, ATOM);
+                self.out.push('(');
+                for (i, argument) in call.arguments.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expr(argument);
+                }
+                self.out.push(')');
+            }
+            Term::Print(print) => self.builtin("print", &print.value),
+            Term::First(f) => self.builtin("first", &f.value),
+            Term::Second(s) => self.builtin("second", &s.value),
+            Term::Tuple(t) => {
+                self.out.push('(');
+                self.expr(&t.first);
+                self.out.push_str(", ");
+                self.expr(&t.second);
+                self.out.push(')');
+            }
+            // The parser keeps the text it could not make sense of.
+            Term::Error(e) => self.out.push_str(&e.full_text),
+        }
+    }
+
+    fn builtin(&mut self, name: &str, value: &Term) {
+        self.out.push_str(name);
+        self.out.push('(');
+        self.expr(value);
+        self.out.push(')');
+    }
+
+    /// Render a term, parenthesized if it binds less tightly than `min`.
+    fn operand(&mut self, term: &Term, min: u8) {
+        match level(term) >= min {
+            true => self.expr(term),
+            false => {
+                self.out.push('(');
+                self.expr(term);
+                self.out.push(')');
+            }
+        }
+    }
+}
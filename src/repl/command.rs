@@ -0,0 +1,62 @@
+/// Commands of the REPL, the lines starting with `:`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command<'a> {
+    /// Evaluate a file into the session.
+    Load(&'a str),
+    /// Evaluate the last loaded file again.
+    Reload,
+    /// Show the AST of an expression.
+    Ast(&'a str),
+    /// Show the type of an expression.
+    Type(&'a str),
+    /// List the bindings of the session.
+    Env,
+    /// Evaluate an expression and show how long it took.
+    Time(&'a str),
+    Help,
+    /// A command that does not exist or misses its argument, with the
+    /// reason.
+    Invalid(String),
+}
+
+pub const HELP: &str = "\
+Commands:
+  :load FILE   evaluate a .rinha or .json file into the session
+  :reload      evaluate the last loaded file again
+  :ast EXPR    show the AST of an expression
+  :type EXPR   show the type of an expression
+  :env         list the bound names and their values
+  :time EXPR   evaluate an expression and show how long it took
+  :help        show this help
+  :quit        leave the REPL";
+
+impl<'a> Command<'a> {
+    /// Parse a line of the REPL, if it is a command.
+    /// ```rust
+    /// use rinha_compiladores::repl::Command;
+    ///
+    /// assert_eq!(Command::parse(":type fn (x) => { x }"), Some(Command::Type("fn (x) => { x }")));
+    /// assert_eq!(Command::parse("1 + 1"), None);
+    /// ```
+    pub fn parse(line: &'a str) -> Option<Command<'a>> {
+        let line = line.trim().strip_prefix(':')?;
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+        let needs = |command: fn(&'a str) -> Command<'a>, what: &str| match argument {
+            "" => Command::Invalid(format!(":{name} needs {what}")),
+            argument => command(argument),
+        };
+        Some(match name {
+            "load" | "l" => needs(Command::Load, "a file"),
+            "reload" | "r" => Command::Reload,
+            "ast" => needs(Command::Ast, "an expression"),
+            "type" | "t" => needs(Command::Type, "an expression"),
+            "env" => Command::Env,
+            "time" => needs(Command::Time, "an expression"),
+            "help" | "h" | "?" => Command::Help,
+            name => Command::Invalid(format!("unknown command :{name}")),
+        })
+    }
+}
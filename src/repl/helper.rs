@@ -0,0 +1,232 @@
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::CharIndices;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Helper};
+
+const KEYWORDS: [&str; 7] = ["let", "fn", "if", "else", "true", "false", "_"];
+const BUILTINS: [&str; 3] = ["print", "first", "second"];
+const COMMANDS: [&str; 8] = [
+    ":load", ":reload", ":ast", ":type", ":env", ":time", ":help", ":quit",
+];
+
+const RESET: &str = "\x1b[0m";
+const MAGENTA: &str = "\x1b[35m";
+const BLUE: &str = "\x1b[34m";
+const YELLOW: &str = "\x1b[33m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const GREY: &str = "\x1b[90m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Keyword,
+    Builtin,
+    Number,
+    Str,
+    Operator,
+    Comment,
+    Open,
+    Close,
+    /// Names, punctuation and whitespace.
+    Other,
+    /// A string or a comment missing its end.
+    Unterminated,
+}
+
+/// Split rinha source in tokens, as far as highlighting and bracket matching
+/// need to. Input the parser would reject is split too.
+fn lex(source: &str) -> Vec<(Token, Range<usize>)> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        // Skip to the next character matching `stop`, returning its index.
+        let end_of = |chars: &mut Peekable<CharIndices>, stop: fn(char) -> bool| {
+            while chars.next_if(|&(_, c)| !stop(c)).is_some() {}
+            chars.peek().map_or(source.len(), |&(i, _)| i)
+        };
+        let token = match c {
+            '"' => {
+                let end = end_of(&mut chars, |c| c == '"');
+                match chars.next() {
+                    Some(_) => (Token::Str, start..end + 1),
+                    None => (Token::Unterminated, start..end),
+                }
+            }
+            '/' if source[start..].starts_with("//") => {
+                (Token::Comment, start..end_of(&mut chars, |c| c == '\n'))
+            }
+            '/' if source[start..].starts_with("/*") => match source[start + 2..].find("*/") {
+                Some(i) => {
+                    let end = start + 2 + i + 2;
+                    while chars.next_if(|&(i, _)| i < end).is_some() {}
+                    (Token::Comment, start..end)
+                }
+                None => {
+                    chars.by_ref().for_each(drop);
+                    (Token::Unterminated, start..source.len())
+                }
+            },
+            '(' | '{' => (Token::Open, start..start + 1),
+            ')' | '}' => (Token::Close, start..start + 1),
+            c if c.is_ascii_digit() => (
+                Token::Number,
+                start..end_of(&mut chars, |c| !c.is_ascii_digit()),
+            ),
+            c if c.is_alphanumeric() || c == '_' => {
+                let end = end_of(&mut chars, |c| !(c.is_alphanumeric() || c == '_'));
+                let word = &source[start..end];
+                match word {
+                    word if KEYWORDS.contains(&word) => (Token::Keyword, start..end),
+                    word if BUILTINS.contains(&word) => (Token::Builtin, start..end),
+                    _ => (Token::Other, start..end),
+                }
+            }
+            c if "+-*/%=!<>&|".contains(c) => (
+                Token::Operator,
+                start..end_of(&mut chars, |c| !"=<>&|".contains(c)),
+            ),
+            c => (Token::Other, start..start + c.len_utf8()),
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Whether more lines are needed to complete the input: brackets are left
+/// open, or a string or a comment is not closed.
+/// ```rust
+/// use rinha_compiladores::repl::incomplete;
+///
+/// assert!(incomplete("let f = fn (x) => {"));
+/// assert!(!incomplete("let f = fn (x) => { x };"));
+/// ```
+pub fn incomplete(source: &str) -> bool {
+    let mut depth = 0;
+    for (token, _) in lex(source) {
+        match token {
+            Token::Open => depth += 1,
+            Token::Close => depth -= 1,
+            Token::Unterminated => return true,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+/// Whether the input ends with a `;`, comments aside, so with a `let`
+/// missing the expression after it.
+pub(crate) fn ends_with_let(source: &str) -> bool {
+    lex(source)
+        .into_iter()
+        .rev()
+        .find(|(token, range)| *token != Token::Comment && !source[range.clone()].trim().is_empty())
+        .is_some_and(|(_, range)| &source[range] == ";")
+}
+
+/// Line editor support for rinha: multi-line input, highlighting and
+/// completion of names.
+#[derive(Default)]
+pub struct RinhaHelper {
+    /// Names bound in the session, to complete.
+    pub names: Vec<String>,
+}
+
+impl RinhaHelper {
+    /// Completions of the word before `pos`, and where it starts.
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+        let start = before
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .map_or(0, |i| i + 1);
+        let word = &before[start..];
+        if word.is_empty() {
+            return (pos, Vec::new());
+        }
+        let words: Vec<&str> = match word.starts_with(':') && start == 0 {
+            true => COMMANDS.to_vec(),
+            false => {
+                let names = self.names.iter().map(String::as_str);
+                let mut words: Vec<&str> = names.chain(BUILTINS).chain(KEYWORDS).collect();
+                words.sort_unstable();
+                words.dedup();
+                words
+            }
+        };
+        let candidates = words
+            .into_iter()
+            .filter(|w| w.starts_with(word) && *w != word)
+            .map(String::from)
+            .collect();
+        (start, candidates)
+    }
+}
+
+impl Completer for RinhaHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, candidates) = self.candidates(line, pos);
+        let pairs = candidates
+            .into_iter()
+            .map(|c| Pair {
+                display: c.clone(),
+                replacement: c,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Highlighter for RinhaHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        let mut out = String::with_capacity(line.len() * 2);
+        for (token, range) in lex(line) {
+            let color = match token {
+                Token::Keyword => MAGENTA,
+                Token::Builtin => BLUE,
+                Token::Number => YELLOW,
+                Token::Str => GREEN,
+                Token::Operator => CYAN,
+                Token::Comment | Token::Unterminated => GREY,
+                Token::Open | Token::Close | Token::Other => {
+                    out.push_str(&line[range]);
+                    continue;
+                }
+            };
+            out.push_str(color);
+            out.push_str(&line[range]);
+            out.push_str(RESET);
+        }
+        Cow::Owned(out)
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+        true
+    }
+}
+
+impl Validator for RinhaHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        Ok(match incomplete(ctx.input()) {
+            true => ValidationResult::Incomplete,
+            false => ValidationResult::Valid(None),
+        })
+    }
+}
+
+impl Hinter for RinhaHelper {
+    type Hint = String;
+}
+
+impl Helper for RinhaHelper {}
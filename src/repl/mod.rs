@@ -0,0 +1,166 @@
+mod command;
+mod helper;
+mod session;
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use rustyline::error::ReadlineError;
+use rustyline::history::FileHistory;
+use rustyline::Editor;
+
+pub use self::command::{Command, HELP};
+pub use self::helper::{incomplete, RinhaHelper};
+pub use self::session::{Outcome, Session, SessionError};
+use crate::eval::runtime::Interrupt;
+
+/// File the inputs are saved to between sessions, in the home directory.
+const HISTORY_FILE: &str = ".rinha_history";
+
+/// Interrupt of the session of the REPL, for the SIGINT handler.
+static INTERRUPT: OnceLock<Interrupt> = OnceLock::new();
+
+/// Make Ctrl-C stop the evaluation in progress instead of the process. At
+/// the prompt, the line editor reads Ctrl-C as a key instead.
+#[cfg(unix)]
+fn catch_interrupts(interrupt: Interrupt) {
+    extern "C" fn on_sigint(_: libc::c_int) {
+        if let Some(interrupt) = INTERRUPT.get() {
+            interrupt.interrupt();
+        }
+    }
+    if INTERRUPT.set(interrupt).is_ok() {
+        // SAFETY: the handler only stores to an atomic.
+        let handler: extern "C" fn(libc::c_int) = on_sigint;
+        unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
+    }
+}
+
+#[cfg(not(unix))]
+fn catch_interrupts(_interrupt: Interrupt) {}
+
+fn history_path() -> Option<PathBuf> {
+    Some(home::home_dir()?.join(HISTORY_FILE))
+}
+
+/// Run the interactive REPL until the user quits.
+pub fn run() {
+    println!(
+        r"
+     ____  _       _
+    |  _ \(_)_ __ | |__   __ _
+    | |_) | | '_ \| '_ \ / _` |
+    |  _ <| | | | | | | | (_| |
+    |_| \_\_|_| |_|_| |_|\__,_|
+    
+      ____                      _ _           _
+     / ___|___  _ __ ___  _ __ (_) | __ _  __| | ___  _ __ ___  ___
+    | |   / _ \| '_ ` _ \| '_ \| | |/ _` |/ _` |/ _ \| '__/ _ \/ __|
+    | |__| (_) | | | | | | |_) | | | (_| | (_| | (_) | | |  __/\__ \
+     \____\___/|_| |_| |_| .__/|_|_|\__,_|\__,_|\___/|_|  \___||___/
+                         |_|
+    
+    "
+    );
+    println!("\t\t\t🐔 Welcome to the Rinha REPL!\n\n");
+    let mut session = Session::new();
+    let interrupt = session.interrupt();
+    catch_interrupts(interrupt.clone());
+    let mut rl: Editor<RinhaHelper, FileHistory> = Editor::new().expect("Error");
+    rl.set_helper(Some(RinhaHelper::default()));
+    let history = history_path();
+    if let Some(path) = &history {
+        // There is no history on the first run.
+        let _ = rl.load_history(path);
+    }
+    loop {
+        let readline = rl.readline("👉  ");
+        match readline {
+            Ok(line) => {
+                if ["exit", "quit", "exit()", "quit()", ":quit", ":q"].contains(&line.trim()) {
+                    print!("👋 Bye!");
+                    break;
+                }
+
+                if line == "clear" || line == "cls" {
+                    rl.clear_screen().expect("Error");
+                    continue;
+                }
+
+                interrupt.reset();
+                let result = match Command::parse(&line) {
+                    Some(command) => run_command(&mut session, command),
+                    None => session.eval(&line).map(show),
+                };
+                match result {
+                    Ok(()) => {
+                        rl.add_history_entry(line).expect("Error");
+                    }
+                    Err(e) => {
+                        for report in session.reports(e) {
+                            eprintln!("{:?}", report);
+                        }
+                    }
+                };
+                if let Some(helper) = rl.helper_mut() {
+                    helper.names = session.names();
+                }
+            }
+            Err(ReadlineError::Interrupted) => {
+                println!("CTRL-C, use CTRL-D or :quit to leave");
+                continue;
+            }
+            Err(ReadlineError::Eof) => {
+                println!("CTRL-D");
+                break;
+            }
+            Err(err) => {
+                println!("Error: {:?}", err);
+                break;
+            }
+        }
+    }
+    if let Some(path) = &history {
+        if let Err(err) = rl.save_history(path) {
+            eprintln!("could not save the history to {}: {err}", path.display());
+        }
+    }
+}
+
+fn show(outcome: Outcome) {
+    if let Some(value) = outcome.value {
+        println!("🟰   {}", value);
+    } else if !outcome.defined.is_empty() {
+        println!("🟰   defined {}", outcome.defined.join(", "));
+    }
+}
+
+fn run_command(session: &mut Session, command: Command) -> Result<(), SessionError> {
+    match command {
+        Command::Load(path) => session.load(path).map(show)?,
+        Command::Reload => match session.reload() {
+            Some(outcome) => outcome.map(show)?,
+            None => println!("no file loaded yet, use :load FILE"),
+        },
+        Command::Ast(source) => println!("{:#?}", session.parse(source)?),
+        Command::Type(source) => println!("{}", session.type_of(source)?),
+        Command::Env => {
+            let mut bindings = session.bindings();
+            bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (name, value) in bindings {
+                println!("{name} = {value}");
+            }
+        }
+        Command::Time(source) => {
+            let start = Instant::now();
+            let outcome = session.eval(source);
+            let elapsed = start.elapsed();
+            outcome.map(show)?;
+            println!("⏱️   {:?}", elapsed);
+        }
+        Command::Help => println!("{HELP}"),
+        Command::Invalid(reason) => println!("{reason}\n\n{HELP}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use miette::{Diagnostic, NamedSource, Report};
+use rinha::parser::ParseError;
+use thiserror::Error;
+
+use super::helper::ends_with_let;
+use crate::ast::{Element, File, Let, Location, Term, Var};
+use crate::check::types::Type;
+use crate::check::{check, TypeError};
+use crate::eval::core::eval_top_level;
+use crate::eval::env::Env;
+use crate::eval::error::RuntimeError;
+use crate::eval::runtime::{Interrupt, Runtime};
+use crate::eval::val::Val;
+
+/// Error of an input of a [`Session`].
+#[derive(Debug, Error, Diagnostic)]
+pub enum SessionError {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] ParseError),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Runtime(#[from] RuntimeError),
+
+    #[error("{} type errors", .0.len())]
+    Type(Vec<TypeError>),
+
+    #[error("could not read {path}: {error}")]
+    Read {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    #[error("invalid AST in {path}: {error}")]
+    Json {
+        path: PathBuf,
+        error: serde_json::Error,
+    },
+}
+
+/// What evaluating an input of a [`Session`] gave.
+#[derive(Debug)]
+pub struct Outcome {
+    /// Value of the input, if there is one to show: inputs ending with a
+    /// `let` have none, and `print`s already showed theirs.
+    pub value: Option<Val>,
+    /// Names bound by the `let`s the input starts with, in order.
+    pub defined: Vec<String>,
+}
+
+/// An interactive session, where the `let`s of an input stay bound for the
+/// next ones.
+/// ```rust
+/// use rinha_compiladores::repl::Session;
+///
+/// let mut session = Session::new();
+/// session.eval("let inc = fn (x) => { x + 1 };").expect("error");
+/// let outcome = session.eval("inc(41)").expect("error");
+///
+/// assert_eq!(outcome.value.expect("no value").to_string(), "42");
+/// assert_eq!(session.names(), ["inc"]);
+/// ```
+#[derive(Default)]
+pub struct Session {
+    env: Env,
+    rt: Runtime,
+    /// The `let`s bound so far, oldest first, to type check inputs with.
+    definitions: Vec<(Var, Term)>,
+    /// Text of the inputs and files evaluated so far, by the name they were
+    /// parsed with, to show errors in.
+    sources: HashMap<String, String>,
+    inputs: usize,
+    loaded: Option<PathBuf>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse an input. Unlike in a file, the input can end with a `let` that
+    /// has no expression after it, which is given a `0` one.
+    pub fn parse(&mut self, source: &str) -> Result<Term, SessionError> {
+        self.inputs += 1;
+        let name = format!("<repl:{}>", self.inputs);
+        // On a line of its own, the `0` is not part of a comment ending the
+        // input.
+        let file = match ends_with_let(source) {
+            true => rinha::parser::parse_or_report(&name, &format!("{source}\n0"))?,
+            false => rinha::parser::parse_or_report(&name, source)?,
+        };
+        self.sources.insert(name, source.to_string());
+        Ok(Term::from(file.expression))
+    }
+
+    /// Evaluate an input, see [`Session::parse`].
+    pub fn eval(&mut self, source: &str) -> Result<Outcome, SessionError> {
+        let term = self.parse(source)?;
+        let trailing_let = ends_with_let(source);
+        self.eval_term(term, trailing_let)
+    }
+
+    /// Evaluate a program file, rinha source or a JSON AST, binding its
+    /// leading `let`s. The file is remembered for [`Session::reload`].
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<Outcome, SessionError> {
+        let path = path.as_ref();
+        let read = |error| SessionError::Read {
+            path: path.to_path_buf(),
+            error,
+        };
+        let text = fs::read_to_string(path).map_err(read)?;
+        let name = path.display().to_string();
+        let file = match path.extension().is_some_and(|e| e == "json") {
+            true => File::from_json(&text).map_err(|error| SessionError::Json {
+                path: path.to_path_buf(),
+                error,
+            })?,
+            false => {
+                let file = File::from(rinha::parser::parse_or_report(&name, &text)?);
+                self.sources.insert(name, text);
+                file
+            }
+        };
+        self.loaded = Some(path.to_path_buf());
+        self.eval_term(file.expression, false)
+    }
+
+    /// Evaluate the last loaded file again, if any.
+    pub fn reload(&mut self) -> Option<Result<Outcome, SessionError>> {
+        let path = self.loaded.clone()?;
+        Some(self.load(path))
+    }
+
+    /// Flag stopping the evaluation in progress, see [`Interrupt`].
+    pub fn interrupt(&self) -> Interrupt {
+        self.rt.interrupt.clone()
+    }
+
+    /// The last loaded file.
+    pub fn loaded(&self) -> Option<&Path> {
+        self.loaded.as_deref()
+    }
+
+    fn eval_term(&mut self, term: Term, trailing_let: bool) -> Result<Outcome, SessionError> {
+        let mut definitions = Vec::new();
+        let mut last = &term;
+        while let Term::Let(l) = last {
+            definitions.push((l.name.clone(), l.value.as_ref().clone()));
+            last = &l.next;
+        }
+        let shown = !trailing_let && !matches!(last, Term::Print(_));
+
+        let (value, env) = eval_top_level(term, &self.env, &mut self.rt)?;
+        self.env = env;
+        let defined = definitions
+            .iter()
+            .map(|(name, _)| name.text.clone())
+            .collect();
+        self.definitions.extend(definitions);
+        Ok(Outcome {
+            value: shown.then_some(value),
+            defined,
+        })
+    }
+
+    /// Infer the type of an input, given the definitions of the session.
+    /// Definitions that do not type check are left out, their names are
+    /// unbound for the input.
+    pub fn type_of(&mut self, source: &str) -> Result<Type, SessionError> {
+        let term = self.parse(source)?;
+        let mut context = Vec::new();
+        // Functions waiting for the rest of their recursive group.
+        let mut pending = Vec::new();
+        for definition in &self.definitions {
+            let (name, _) = definition;
+            let checks = |definitions: &[&(Var, Term)]| {
+                let candidate: Vec<_> = context.iter().chain(definitions).copied().collect();
+                check(&bind(&candidate, Term::Var(name.clone()))).is_ok()
+            };
+            pending.push(definition);
+            if checks(&pending) {
+                context.append(&mut pending);
+            } else if checks(&[definition]) {
+                pending.clear();
+                context.push(definition);
+            }
+        }
+        check(&bind(&context, term)).map_err(SessionError::Type)
+    }
+
+    /// Names bound in the session, the most recent first.
+    pub fn names(&self) -> Vec<String> {
+        self.env.names()
+    }
+
+    /// Values of the names bound in the session, the most recent first.
+    pub fn bindings(&self) -> Vec<(String, Val)> {
+        let names = self.names().into_iter();
+        names
+            .filter_map(|name| Some((name.clone(), self.env.get(&name)?)))
+            .collect()
+    }
+
+    /// Diagnostics of an error, showing the input or the file each of them
+    /// is about.
+    pub fn reports(&self, error: SessionError) -> Vec<Report> {
+        match error {
+            SessionError::Runtime(e) => {
+                let location = e.location().cloned();
+                vec![self.with_source(Report::new(e), location.as_ref())]
+            }
+            SessionError::Type(errors) => errors
+                .into_iter()
+                .map(|e| {
+                    let location = e.location().clone();
+                    self.with_source(Report::new(e), Some(&location))
+                })
+                .collect(),
+            // Parse errors come with their source.
+            error => vec![Report::new(error)],
+        }
+    }
+
+    fn with_source(&self, report: Report, location: Option<&Location>) -> Report {
+        let source = location.and_then(|l| Some((&l.filename, self.sources.get(&l.filename)?)));
+        match source {
+            Some((name, text)) => report.with_source_code(NamedSource::new(name, text.clone())),
+            None => report,
+        }
+    }
+}
+
+/// Wrap a term in `let`s binding the definitions, the first outermost.
+fn bind(definitions: &[&(Var, Term)], term: Term) -> Term {
+    definitions.iter().rev().fold(term, |next, (name, value)| {
+        Term::Let(Let {
+            name: name.clone(),
+            location: value.location().clone(),
+            value: Box::new(value.clone()),
+            next: Box::new(next),
+        })
+    })
+}
@@ -0,0 +1,32 @@
+use rinha_compiladores::ast::File;
+
+fn parse(name: &str, source: &str) -> File {
+    File::from(rinha::parser::parse_or_report(name, source).expect("parse error"))
+}
+
+#[test]
+fn official_format_test() {
+    let source = std::fs::read_to_string("examples/source.rinha").expect("missing example");
+    let json = std::fs::read_to_string("examples/source.rinha.json").expect("missing example");
+    let file = parse("./examples/source.rinha", &source);
+    assert_eq!(file.to_json(false).unwrap(), json.trim_end());
+}
+
+#[test]
+fn pretty_test() {
+    let file = parse("a.rinha", "print(1)");
+    let pretty = file.to_json(true).unwrap();
+    assert!(pretty
+        .starts_with("{\n  \"name\": \"a.rinha\",\n  \"expression\": {\n    \"kind\": \"Print\","));
+    let read = File::from_json(&pretty).unwrap();
+    assert_eq!(read.to_json(false).unwrap(), file.to_json(false).unwrap());
+}
+
+#[test]
+fn deep_program_test() {
+    // Deep enough to overflow the stack of a test thread without growing it.
+    let source = format!("{}0", "let x = 1;\n".repeat(10_000));
+    let json = parse("deep.rinha", &source).to_json(false).unwrap();
+    let read = File::from_json(&json).unwrap();
+    assert_eq!(read.to_json(false).unwrap(), json);
+}
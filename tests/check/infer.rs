@@ -0,0 +1,131 @@
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::check::{check, TypeError};
+
+fn parse(source: &str) -> Term {
+    let file = rinha::parser::parse_or_report("check_test", source).expect("parse error");
+    Term::from(file.expression)
+}
+
+fn type_of(source: &str) -> String {
+    match check(&parse(source)) {
+        Ok(ty) => ty.to_string(),
+        Err(errors) => panic!("type errors in {source}: {errors:?}"),
+    }
+}
+
+fn type_errors(source: &str) -> Vec<TypeError> {
+    check(&parse(source)).expect_err("the program type checks")
+}
+
+#[test]
+fn infer_test() {
+    assert_eq!(type_of("1"), "int");
+    assert_eq!(type_of("(true, \"a\")"), "(bool, str)");
+    assert_eq!(type_of("fn (x) => { x }"), "('a) -> 'a");
+    assert_eq!(
+        type_of("fn (f, g) => { fn (x) => { f(g(x)) } }"),
+        "(('a) -> 'b, ('c) -> 'a) -> ('c) -> 'b"
+    );
+    assert_eq!(
+        type_of("let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib"),
+        "(int) -> int"
+    );
+}
+
+#[test]
+fn let_polymorphism_test() {
+    assert_eq!(
+        type_of("let id = fn (x) => { x }; (id(1), id(true))"),
+        "(int, bool)"
+    );
+    assert_eq!(
+        type_of("let swap = fn (p) => { (second(p), first(p)) }; swap((1, \"a\"))"),
+        "(str, int)"
+    );
+    // Parameters are not generalized.
+    assert!(!type_errors("fn (id) => { (id(1), id(true)) }").is_empty());
+}
+
+#[test]
+fn overloading_test() {
+    assert_eq!(type_of("1 + 2"), "int");
+    assert_eq!(type_of("\"a\" + 1"), "str");
+    assert_eq!(type_of("let p = (1, 2); p + \"a\""), "str");
+    assert_eq!(type_of("fn (a, b) => { a + b }"), "(int, int) -> int");
+    assert_eq!(type_of("fn (name) => { \"hello \" + name }"), "('a) -> str");
+    assert_eq!(type_of("fn (a) => { a + true }"), "(str) -> str");
+    assert_eq!(type_of("first(\"abc\")"), "str");
+    assert_eq!(type_of("fn (s) => { first(s) + 1 }"), "((int, 'a)) -> int");
+
+    let errors = type_errors("1 + true");
+    assert!(matches!(&errors[..], [TypeError::Add { .. }]));
+}
+
+#[test]
+fn type_error_test() {
+    let source = "let f = fn (x) => { x - 1 }; f(true)";
+    match &type_errors(source)[..] {
+        [TypeError::Mismatch {
+            expected,
+            actual,
+            location,
+        }] => {
+            assert_eq!(
+                (expected.to_string(), actual.to_string()),
+                ("int".into(), "bool".into())
+            );
+            assert_eq!(&source[location.start..location.end], "true");
+        }
+        errors => panic!("unexpected errors {errors:?}"),
+    }
+
+    let errors = type_errors("if (true) { 1 } else { \"a\" }");
+    assert_eq!(
+        errors[0].to_string(),
+        "type mismatch: expected int, found str"
+    );
+
+    let errors = type_errors("let f = fn (x) => { x(x) }; f");
+    assert!(matches!(&errors[..], [TypeError::Infinite { .. }]));
+
+    let errors = type_errors("a + b");
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(&errors[0], TypeError::Unbound { name, .. } if name == "a"));
+}
+
+#[test]
+fn source_example_test() {
+    // Lists made of nested tuples need recursive types.
+    let source = std::fs::read_to_string("examples/source.rinha").expect("missing example");
+    let errors = type_errors(&source);
+    assert!(matches!(errors[0], TypeError::Infinite { .. }));
+}
+
+#[test]
+fn mutual_recursion_test() {
+    let source = r#"
+        let even = fn (n) => { if (n == 0) { true } else { odd(n - 1) } };
+        let odd = fn (n) => { if (n == 0) { false } else { even(n - 1) } };
+        (even, odd)
+    "#;
+    assert_eq!(type_of(source), "((int) -> bool, (int) -> bool)");
+    // The group is generalized together.
+    let source = r#"
+        let apply = fn (f, x) => { if (true) { f(x) } else { again(f, x) } };
+        let again = fn (f, x) => { apply(f, x) };
+        (apply(fn (x) => { x }, 1), again(fn (x) => { x }, true))
+    "#;
+    assert_eq!(type_of(source), "(int, bool)");
+    // A later function does not join the group in place of a bound name.
+    assert_eq!(
+        type_of("let g = 5; let f = fn () => { g }; let g = fn () => { 1 }; f()"),
+        "int"
+    );
+}
+
+#[test]
+fn deep_let_test() {
+    // Inferring recurses once per `let`, on a stack grown as needed.
+    let lets: String = (0..20_000).map(|i| format!("let x{i} = {i};\n")).collect();
+    assert_eq!(type_of(&format!("{lets}x0 + x19999")), "int");
+}
@@ -0,0 +1,44 @@
+use std::process::{Child, Command, Output, Stdio};
+
+const SOURCE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/source.rinha");
+const JSON: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/source.rinha.json");
+
+/// Start the `main` binary, so that the runs of a test overlap.
+fn main(args: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_main"))
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("could not run main")
+}
+
+fn output(child: Child) -> Output {
+    child.wait_with_output().expect("could not run main")
+}
+
+#[test]
+fn source_test() {
+    // The arguments of `run.sh` in the Dockerfile.
+    let json = main(&["--wrapping", JSON]);
+    let vm = main(&["run", "--vm", "--wrapping", SOURCE]);
+    // `pow` overflows, which fails without `--wrapping`.
+    let strict = main(&[SOURCE]);
+
+    let json = output(json);
+    assert!(
+        json.status.success(),
+        "{}",
+        String::from_utf8_lossy(&json.stderr)
+    );
+    let stdout = String::from_utf8(json.stdout).expect("invalid output");
+    assert!(stdout.starts_with("@!compile::\n"), "{stdout}");
+    assert!(stdout.ends_with("@!dynamic_list_run::\n"), "{stdout}");
+    let vm = output(vm);
+    assert!(vm.status.success());
+    assert_eq!(String::from_utf8_lossy(&vm.stdout), stdout);
+
+    let strict = output(strict);
+    assert_eq!(strict.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&strict.stderr).contains("integer overflow"));
+}
@@ -1,7 +1,229 @@
-use rinha_compiladores::interpreter::interpreter;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use rinha_compiladores::ast::File;
+use rinha_compiladores::convert::IntoVal;
+use rinha_compiladores::error::RuntimeError;
+use rinha_compiladores::interpreter::{interpreter, Interpreter, InterpreterError};
+use rinha_compiladores::output::Buffer;
+use rinha_compiladores::val::Val;
 
 #[test]
 fn interpreter_test() {
     let result = interpreter("1 + 1".to_string().as_str()).expect("error");
-    assert_eq!(result, "Int(2)".to_string());
+    assert!(matches!(result.value, Val::Int(2)));
+    assert_eq!(result.to_string(), "2");
+    assert_eq!(result.output, "");
+}
+
+#[test]
+fn output_test() {
+    let result = interpreter("let _ = print((1, \"a\")); print(print(true))").expect("error");
+    assert_eq!(result.output, "(1, a)\ntrue\ntrue\n");
+    assert_eq!(result.to_string(), "true");
+}
+
+#[test]
+fn parse_error_test() {
+    let error = interpreter("let x = ;").unwrap_err();
+    assert!(matches!(error, InterpreterError::Parse(_)));
+}
+
+#[test]
+fn runtime_error_test() {
+    let error = interpreter("let x = 0;\n1 % x").unwrap_err();
+    assert!(matches!(error, InterpreterError::Runtime { .. }));
+    assert!(matches!(
+        error.runtime_error(),
+        Some(RuntimeError::DivisionByZero { .. })
+    ));
+    // The error renders with the line it happened at.
+    let handler =
+        miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor());
+    let mut rendered = String::new();
+    handler.render_report(&mut rendered, &error).unwrap();
+    assert!(rendered.contains("1 % x"), "{rendered}");
+    assert!(rendered.contains("the divisor is zero"), "{rendered}");
+}
+
+#[test]
+fn native_test() {
+    let calls = Rc::new(Cell::new(0));
+    let counter = calls.clone();
+    let mut interpreter = Interpreter::builder()
+        .memo(true)
+        .output(Buffer::default())
+        .function("count", 1, move |args| {
+            counter.set(counter.get() + 1);
+            Ok(args[0].clone())
+        })
+        .function("join", 2, |args| {
+            Ok(Val::Str(format!("{}-{}", args[0], args[1])))
+        })
+        .build();
+    let value = interpreter
+        .eval("let f = fn (x) => { count(x) }; (f(1), (f(1), join(\"a\", 2)))")
+        .expect("error");
+    assert_eq!(value.to_string(), "(1, (1, a-2))");
+    // Natives are impure, the calls through `f` are not memoized.
+    assert_eq!(calls.get(), 2);
+    assert_eq!(
+        interpreter.eval("count").expect("error").to_string(),
+        "<#closure>"
+    );
+}
+
+#[test]
+fn native_error_test() {
+    let mut interpreter = Interpreter::builder()
+        .function("fail", 1, |args| Err(format!("failed with {}", args[0])))
+        .build();
+    let error = interpreter.eval("let x = 1;\nfail(x)").unwrap_err();
+    match error.runtime_error() {
+        Some(RuntimeError::Native {
+            name,
+            message,
+            location,
+        }) => {
+            assert_eq!(name, "fail");
+            assert_eq!(message, "failed with 1");
+            assert_eq!(location.start, 11);
+        }
+        error => panic!("unexpected error {error:?}"),
+    }
+    let error = interpreter.eval("fail()").unwrap_err();
+    assert!(matches!(
+        error.runtime_error(),
+        Some(RuntimeError::Arity {
+            expected: 1,
+            actual: 0,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn reuse_test() {
+    let mut interpreter = Interpreter::builder()
+        .define("base", Val::Int(10))
+        .output(Buffer::default())
+        .build();
+    interpreter
+        .eval("let add = fn (x) => { x + base }; let broken = fn () => { 1 % 0 }; 0")
+        .expect("error");
+    assert_eq!(interpreter.eval("add(5)").expect("error").to_string(), "15");
+    assert!(matches!(interpreter.get("add"), Some(Val::Closure { .. })));
+
+    interpreter.define("base", Val::Int(20));
+    assert_eq!(interpreter.eval("add(5)").expect("error").to_string(), "15");
+    assert_eq!(interpreter.eval("base").expect("error").to_string(), "20");
+
+    // Errors show the program the failing code comes from.
+    let error = interpreter.eval("broken()").unwrap_err();
+    let handler =
+        miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor());
+    let mut rendered = String::new();
+    handler.render_report(&mut rendered, &error).unwrap();
+    assert!(rendered.contains("<input:1>"), "{rendered}");
+}
+
+#[test]
+fn eval_file_test() {
+    let mut interpreter = Interpreter::default();
+    let file =
+        rinha::parser::parse_or_report("parsed", "let one = 1; one % 0").expect("parse error");
+    let error = interpreter.eval_file(File::from(file)).unwrap_err();
+    assert!(matches!(
+        error,
+        InterpreterError::Unsourced(RuntimeError::DivisionByZero { .. })
+    ));
+    let file = rinha::parser::parse_or_report("parsed", "let two = 2; two").expect("parse error");
+    interpreter.eval_file(File::from(file)).expect("error");
+    assert_eq!(interpreter.eval("two + 1").expect("error").to_string(), "3");
+}
+
+#[test]
+fn call_test() {
+    let output = Buffer::default();
+    let mut interpreter = Interpreter::builder().output(output.clone()).build();
+    interpreter
+        .eval(
+            r#"
+            let by_length = fn (a, b) => { second(a) < second(b) };
+            let describe = fn (pair) => { let _ = print(first(pair)); first(pair) + ": " + second(pair) };
+            0
+            "#,
+        )
+        .expect("error");
+
+    let by_length = interpreter.get("by_length").expect("unbound");
+    let mut words = vec![("c", 3), ("a", 1), ("b", 2)];
+    words.sort_by(|a, b| {
+        let less: bool = interpreter
+            .call(&by_length, vec![a.into_val(), b.into_val()])
+            .expect("error");
+        match less {
+            true => Ordering::Less,
+            false => Ordering::Greater,
+        }
+    });
+    assert_eq!(words, [("a", 1), ("b", 2), ("c", 3)]);
+
+    let describe = interpreter.get("describe").expect("unbound");
+    let text: String = interpreter
+        .call(&describe, vec![("x", 1).into_val()])
+        .expect("error");
+    assert_eq!(text, "x: 1");
+    assert_eq!(output.take(), "x\n");
+}
+
+#[test]
+fn call_error_test() {
+    let mut interpreter = Interpreter::default();
+    let f = interpreter
+        .eval("fn (a, b) => {\n  a % b\n}")
+        .expect("error");
+
+    let error = interpreter.call::<Val>(&f, vec![Val::Int(1)]).unwrap_err();
+    assert!(matches!(
+        error,
+        InterpreterError::Unsourced(RuntimeError::Arity {
+            expected: 2,
+            actual: 1,
+            ..
+        })
+    ));
+
+    let error = interpreter
+        .call::<Val>(&f, vec![Val::Int(1), Val::Int(2), Val::Int(3)])
+        .unwrap_err();
+    assert!(matches!(
+        error.runtime_error(),
+        Some(RuntimeError::Arity {
+            expected: 2,
+            actual: 3,
+            ..
+        })
+    ));
+
+    let error = interpreter
+        .call::<Val>(&f, vec![Val::Int(1), Val::Int(0)])
+        .unwrap_err();
+    assert!(matches!(error, InterpreterError::Runtime { .. }));
+
+    let error = interpreter
+        .call::<bool>(&f, vec![Val::Int(5), Val::Int(3)])
+        .unwrap_err();
+    assert_eq!(error.to_string(), "expected bool, found int");
+
+    let error = interpreter.call::<Val>(&Val::Int(1), vec![]).unwrap_err();
+    assert!(matches!(
+        error.runtime_error(),
+        Some(RuntimeError::TypeMismatch {
+            expected: "closure",
+            actual: "int",
+            ..
+        })
+    ));
 }
@@ -1 +1,2 @@
 mod interpreter_test;
+mod vm_test;
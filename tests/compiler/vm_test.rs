@@ -0,0 +1,54 @@
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::codegen::compile;
+use rinha_compiladores::vm::run;
+
+fn run_source(source: &str) -> String {
+    let file = rinha::parser::parse_or_report("vm_test", source).expect("parse error");
+    let result = run(compile(&Term::from(file.expression))).expect("error on execution");
+    format!("{:?}", result)
+}
+
+#[test]
+fn vm_test() {
+    assert_eq!(run_source("1 + 1"), "Int(2)");
+    assert_eq!(run_source(r#""a" + 1"#), r#"Str("a1")"#);
+    assert_eq!(
+        run_source("if (1 < 2) { true } else { false }"),
+        "Bool(true)"
+    );
+}
+
+#[test]
+fn vm_closure_test() {
+    let source = r#"
+        let add = fn (x) => {
+            fn (y) => { x + y }
+        };
+        let fib = fn (n) => {
+            if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }
+        };
+        (add(1)(2), fib(15))
+    "#;
+    assert_eq!(run_source(source), run_source("(3, 610)"));
+}
+
+#[test]
+fn vm_deep_recursion_test() {
+    let source = r#"
+        let sum = fn (n) => {
+            if (n == 0) { 0 } else { n + sum(n - 1) }
+        };
+        let count = fn (n, acc) => {
+            if (n == 0) { acc } else { count(n - 1, acc + 1) }
+        };
+        (sum(50000), count(1000000, 0))
+    "#;
+    assert_eq!(run_source(source), run_source("(1250025000, 1000000)"));
+}
+
+#[test]
+fn vm_deep_let_test() {
+    // Compiling and dropping the term recurse once per `let`.
+    let lets: String = (0..20_000).map(|i| format!("let x{i} = {i};\n")).collect();
+    assert_eq!(run_source(&format!("{lets}x0 + x19999")), "Int(19999)");
+}
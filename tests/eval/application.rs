@@ -0,0 +1,111 @@
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::core::eval_with;
+use rinha_compiladores::env::Env;
+use rinha_compiladores::error::RuntimeError;
+use rinha_compiladores::eval_call::Application;
+use rinha_compiladores::interpreter::Interpreter;
+use rinha_compiladores::memo::Memo;
+use rinha_compiladores::runtime::Runtime;
+use rinha_compiladores::val::Val;
+
+use super::{both_with, Settings};
+
+const ADD3: &str = "let add3 = fn (a, b, c) => { a * 100 + b * 10 + c };";
+
+/// Run a program with partial application on both backends.
+fn curried(source: &str) -> Result<String, RuntimeError> {
+    let settings = Settings {
+        application: Application::Partial,
+        ..Default::default()
+    };
+    both_with(source, settings).map(|(value, _)| value)
+}
+
+fn value(source: &str) -> String {
+    curried(source).expect("error")
+}
+
+#[test]
+fn partial_application_test() {
+    assert_eq!(value(&format!("{ADD3} add3(1)(2)(3)")), "123");
+    assert_eq!(value(&format!("{ADD3} add3(1, 2)(3)")), "123");
+    assert_eq!(value(&format!("{ADD3} add3(1)(2, 3)")), "123");
+    assert_eq!(value(&format!("{ADD3} add3()(1, 2, 3)")), "123");
+    assert_eq!(
+        value(&format!("{ADD3} let f = add3(1); (f(2, 3), f(4)(5))")),
+        "(123, 145)"
+    );
+    assert_eq!(value(&format!("{ADD3} add3(1)")), "<#closure>");
+}
+
+#[test]
+fn tail_position_test() {
+    let source = format!(
+        r#"
+        {ADD3}
+        let part = fn (a) => {{ add3(a) }};
+        let sum = fn (f, n) => {{ if (n == 0) {{ 0 }} else {{ f(n) + sum(f, n - 1) }} }};
+        sum(part(1)(2), 2)
+    "#
+    );
+    assert_eq!(value(&source), "243");
+}
+
+#[test]
+fn partial_errors_test() {
+    // Extra arguments still fail.
+    let source = format!("{ADD3} add3(1)(2, 3, 4)");
+    let error = curried(&source).unwrap_err();
+    assert!(matches!(
+        error,
+        RuntimeError::Arity {
+            expected: 2,
+            actual: 3,
+            ..
+        }
+    ));
+    // Errors of the call are reported where the last arguments are given.
+    let source = "let div = fn (a, b) => { a / b }; let f = div(1); f(0)";
+    let error = curried(source).unwrap_err();
+    assert!(matches!(error, RuntimeError::DivisionByZero { .. }));
+    let location = error.location().expect("missing location");
+    assert_eq!(&source[location.start..location.end], "a / b");
+}
+
+#[test]
+fn partial_memo_test() {
+    let mut rt = Runtime {
+        application: Application::Partial,
+        memo: Memo::new(true),
+        ..Default::default()
+    };
+    let source = r#"
+        let add = fn (a, b) => { a + b };
+        let inc = add(1);
+        let twice = fn (n) => { inc(n) + inc(n) };
+        twice(5)
+    "#;
+    let file = rinha::parser::parse_or_report("application_test", source).expect("parse error");
+    let result = eval_with(Term::from(file.expression), &Env::new(), &mut rt).expect("error");
+    assert_eq!(result.to_string(), "12");
+    assert!(rt.memo.hits > 0);
+}
+
+#[test]
+fn partial_native_test() {
+    let mut interpreter = Interpreter::builder()
+        .application(Application::Partial)
+        .function("sub", 2, |args| match args {
+            [Val::Int(a), Val::Int(b)] => Ok(Val::Int(a - b)),
+            _ => Err("expected ints".to_string()),
+        })
+        .build();
+    let value = interpreter.eval("let dec = fn (n) => { sub(n)(1) }; dec(10)");
+    assert_eq!(value.expect("error").to_string(), "9");
+
+    let from_ten = interpreter.eval("sub(10)").expect("error");
+    let result: i32 = interpreter
+        .call(&from_ten, vec![Val::Int(3)])
+        .expect("error");
+    assert_eq!(result, 7);
+}
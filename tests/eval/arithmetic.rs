@@ -0,0 +1,107 @@
+use rinha_compiladores::error::RuntimeError;
+use rinha_compiladores::eval_binary::Arithmetic;
+
+use super::{both_with, Settings};
+
+/// `i32::MIN`, which has no literal. Operators are right associative.
+const MIN: &str = "((0 - 2147483647) - 1)";
+const MAX: &str = "2147483647";
+
+/// Run a program on both backends with `arithmetic`.
+fn run(source: &str, arithmetic: Arithmetic) -> Result<String, RuntimeError> {
+    let settings = Settings {
+        arithmetic,
+        ..Default::default()
+    };
+    both_with(source, settings).map(|(value, _)| value)
+}
+
+fn checked(source: &str) -> Result<String, RuntimeError> {
+    run(source, Arithmetic::Checked)
+}
+
+fn wrapping(source: &str) -> Result<String, RuntimeError> {
+    run(source, Arithmetic::Wrapping)
+}
+
+#[test]
+fn in_range_test() {
+    for arithmetic in [Arithmetic::Checked, Arithmetic::Wrapping] {
+        let value = |source: &str| run(source, arithmetic).expect("error");
+        assert_eq!(value(&format!("({MAX} - 1) + 1")), MAX);
+        assert_eq!(value(&format!("({MIN} + 1) - 1")), "-2147483648");
+        assert_eq!(value("46340 * 46340"), "2147395600");
+        assert_eq!(value(&format!("{MIN} / 1")), "-2147483648");
+        assert_eq!(value(&format!("{MAX} / (0 - 1)")), "-2147483647");
+        assert_eq!(value("(0 - 7) / 2"), "-3");
+        assert_eq!(value("(0 - 7) % 2"), "-1");
+        assert_eq!(value("7 % (0 - 2)"), "1");
+    }
+}
+
+#[test]
+fn overflow_test() {
+    for source in [
+        format!("{MAX} + 1"),
+        format!("{MIN} - 1"),
+        format!("0 - {MIN}"),
+        "65536 * 32768".to_string(),
+        format!("{MIN} * (0 - 1)"),
+        format!("{MIN} / (0 - 1)"),
+        format!("{MIN} % (0 - 1)"),
+    ] {
+        match checked(&source) {
+            Err(RuntimeError::Overflow { location }) => {
+                assert_eq!(
+                    (location.start, location.end),
+                    (0, source.len()),
+                    "{source}"
+                );
+            }
+            result => panic!("{source} did not overflow: {result:?}"),
+        }
+    }
+}
+
+#[test]
+fn wrapping_test() {
+    assert_eq!(wrapping(&format!("{MAX} + 1")).unwrap(), "-2147483648");
+    assert_eq!(wrapping(&format!("{MIN} - 1")).unwrap(), MAX);
+    assert_eq!(wrapping("65536 * 32768").unwrap(), "-2147483648");
+    assert_eq!(
+        wrapping(&format!("{MIN} * (0 - 1)")).unwrap(),
+        "-2147483648"
+    );
+    assert_eq!(
+        wrapping(&format!("{MIN} / (0 - 1)")).unwrap(),
+        "-2147483648"
+    );
+    assert_eq!(wrapping(&format!("{MIN} % (0 - 1)")).unwrap(), "0");
+}
+
+#[test]
+fn division_by_zero_test() {
+    for source in ["1 / 0", "1 % 0", "let z = 0; 0 / z", "let z = 0; 0 % z"] {
+        for result in [checked(source), wrapping(source)] {
+            assert!(
+                matches!(result, Err(RuntimeError::DivisionByZero { .. })),
+                "{source}: {result:?}"
+            );
+        }
+    }
+    assert!(matches!(
+        checked("true / 0"),
+        Err(RuntimeError::TypeMismatch {
+            expected: "int",
+            actual: "bool",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn deep_sum_test() {
+    // Every stage recurses once per operator, on a stack grown as needed.
+    let source = vec!["1"; 20_000].join(" + ");
+    assert_eq!(checked(&source).expect("error"), "20000");
+}
@@ -0,0 +1,143 @@
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{BuildHasher, RandomState};
+
+use rinha_compiladores::convert::IntoVal;
+use rinha_compiladores::error::RuntimeError;
+use rinha_compiladores::interpreter::Interpreter;
+use rinha_compiladores::val::Val;
+
+use super::both;
+
+fn value(source: &str) -> String {
+    both(source).expect("error")
+}
+
+/// `lhs op rhs`, with operands bound first since tuples cannot be operands.
+fn binary(lhs: &str, op: &str, rhs: &str) -> String {
+    format!("let a = {lhs}; let b = {rhs}; a {op} b")
+}
+
+#[test]
+fn equality_test() {
+    let t = "(1, (\"a\", true))";
+    assert_eq!(value(&binary(t, "==", t)), "true");
+    assert_eq!(value(&binary(t, "!=", t)), "false");
+    assert_eq!(value(&binary(t, "==", "(1, (\"a\", false))")), "false");
+    assert_eq!(value(&binary(t, "!=", "(1, (\"b\", true))")), "true");
+    assert_eq!(value("let t = (1, 2); t == t"), "true");
+}
+
+#[test]
+fn ordering_test() {
+    assert_eq!(value(&binary("(1, 2)", "<", "(1, 3)")), "true");
+    assert_eq!(value(&binary("(2, 0)", ">", "(1, 9)")), "true");
+    assert_eq!(value(&binary("((1, 2), 3)", "<=", "((1, 2), 3)")), "true");
+    assert_eq!(value(&binary("(\"b\", 0)", ">=", "(\"a\", 1)")), "true");
+    assert_eq!(value("false < true"), "true");
+    assert_eq!(value("\"abc\" < \"abd\""), "true");
+}
+
+#[test]
+fn mixed_types_test() {
+    // `==` and `!=` fail alike on values of different types.
+    for source in [
+        binary("1", "==", "\"1\""),
+        binary("1", "!=", "\"1\""),
+        binary("(1, 2)", "<", "(1, \"2\")"),
+    ] {
+        let error = both(&source).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                RuntimeError::TypeMismatch {
+                    expected: "int",
+                    actual: "str",
+                    ..
+                }
+            ),
+            "{source}: {error:?}"
+        );
+        let location = error.location().expect("missing location");
+        assert!(source[location.start..location.end].starts_with("a "));
+    }
+    // Tuples stop at their first difference.
+    assert_eq!(value(&binary("(1, 2)", "<", "(2, \"2\")")), "true");
+}
+
+#[test]
+fn closures_test() {
+    let source = "let f = fn () => { 1 }; f == f";
+    let error = both(source).unwrap_err();
+    assert!(matches!(error, RuntimeError::Incomparable { .. }));
+    let location = error.location().expect("missing location");
+    assert_eq!(&source[location.start..location.end], "f == f");
+
+    let f = "fn () => { 1 }";
+    let error = both(&binary(&format!("(1, {f})"), "!=", "(1, 0)")).unwrap_err();
+    assert!(matches!(error, RuntimeError::Incomparable { .. }));
+    let source = format!("let f = {f}; {}", binary("(1, f)", "<", "(2, f)"));
+    assert_eq!(value(&source), "true");
+}
+
+// Closures are hashed and ordered by address, not by the frames they
+// capture, so their interior mutability does not affect keys.
+#[allow(clippy::mutable_key_type)]
+#[test]
+fn val_keys_test() {
+    let mut counts: HashMap<Val, usize> = HashMap::new();
+    for val in [(1, "a").into_val(), 2.into_val(), (1, "a").into_val()] {
+        *counts.entry(val).or_default() += 1;
+    }
+    assert_eq!(counts[&(1, "a").into_val()], 2);
+    assert_eq!(counts[&2.into_val()], 1);
+
+    // Values of different types are ordered by type.
+    let sorted: BTreeSet<Val> = [
+        (0, 1).into_val(),
+        "a".into_val(),
+        true.into_val(),
+        2.into_val(),
+        (0, 0).into_val(),
+        1.into_val(),
+    ]
+    .into();
+    let shown: Vec<_> = sorted.iter().map(|val| val.to_string()).collect();
+    assert_eq!(shown, ["1", "2", "true", "a", "(0, 0)", "(0, 1)"]);
+
+    // A closure is only equal to itself.
+    let mut interpreter = Interpreter::default();
+    let f = interpreter
+        .eval("let f = fn () => { 1 }; f")
+        .expect("error");
+    let g = interpreter.eval("fn () => { 1 }").expect("error");
+    assert_eq!(f, interpreter.get("f").expect("unbound"));
+    assert_ne!(f, g);
+}
+
+#[test]
+fn long_list_test() {
+    // Lists are compared, hashed and dropped without recursing on their
+    // rest, so long ones do not overflow the stack.
+    let list = |end: i32| {
+        format!(
+            "let build = fn (n, acc) => {{ if (n == 0) {{ acc }} else {{ build(n - 1, (n, acc)) }} }};
+            build(200000, {end})"
+        )
+    };
+    let source = format!(
+        "let a = {}; let b = {}; (a == b, a < (let c = {}; c))",
+        list(0),
+        list(0),
+        list(1)
+    );
+    assert_eq!(value(&source), "(true, true)");
+
+    let mut interpreter = Interpreter::default();
+    let a = interpreter.eval(&list(0)).expect("error");
+    let b = interpreter.eval(&list(0)).expect("error");
+    let c = interpreter.eval(&list(1)).expect("error");
+    assert_eq!(a, b);
+    assert!(a < c);
+    let state = RandomState::new();
+    assert_eq!(state.hash_one(&a), state.hash_one(&b));
+}
@@ -0,0 +1,51 @@
+use rinha_compiladores::convert::{ConversionError, FromVal, IntoVal};
+use rinha_compiladores::val::Val;
+
+#[test]
+fn round_trip_test() {
+    let val = ((1, true), ("a".to_string(), (2, "b"))).into_val();
+    assert_eq!(val.to_string(), "((1, true), (a, (2, b)))");
+    let back = <((i32, bool), (String, (i32, String)))>::from_val(&val).expect("error");
+    assert_eq!(back, ((1, true), ("a".to_string(), (2, "b".to_string()))));
+    assert!(matches!(Val::from_val(&val), Ok(Val::Tuple(_))));
+}
+
+#[test]
+fn mismatch_test() {
+    assert_eq!(
+        i32::from_val(&"1".into_val()),
+        Err(ConversionError {
+            expected: "int".to_string(),
+            found: "str".to_string(),
+        })
+    );
+    assert_eq!(
+        bool::from_val(&(1, 2).into_val()).unwrap_err().to_string(),
+        "expected bool, found (int, int)"
+    );
+    let nested = (1, (true, "a")).into_val();
+    assert_eq!(
+        <(i32, (bool, i32))>::from_val(&nested)
+            .unwrap_err()
+            .to_string(),
+        "expected (int, (bool, int)), found (int, (bool, str))"
+    );
+    assert_eq!(
+        <(Val, String)>::from_val(&7.into_val())
+            .unwrap_err()
+            .to_string(),
+        "expected (any, str), found int"
+    );
+}
+
+#[test]
+fn long_list_mismatch_test() {
+    let list = (0..200_000)
+        .rev()
+        .fold(0.into_val(), |rest, n| (n, rest).into_val());
+    let found = bool::from_val(&list).unwrap_err().found;
+    assert_eq!(
+        found,
+        format!("{}int{}", "(int, ".repeat(200_000), ")".repeat(200_000))
+    );
+}
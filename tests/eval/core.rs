@@ -1,7 +1,8 @@
-use std::collections::HashMap;
-
 use rinha_compiladores::ast::Term;
-use rinha_compiladores::core::eval;
+use rinha_compiladores::core::{eval, eval_with};
+use rinha_compiladores::env::Env;
+use rinha_compiladores::error::RuntimeError;
+use rinha_compiladores::runtime::Runtime;
 
 #[test]
 fn eval_test() {
@@ -10,7 +11,7 @@ fn eval_test() {
             value: 1,
             ..Default::default()
         })),
-        &mut HashMap::new(),
+        &Env::new(),
     )
     .expect("error on evaluation");
 
@@ -24,7 +25,7 @@ fn eval_test() {
             value: "hello".to_string(),
             ..Default::default()
         })),
-        &mut HashMap::new(),
+        &Env::new(),
     )
     .expect("error on evaluation");
 
@@ -36,3 +37,72 @@ fn eval_test() {
         )
     );
 }
+
+#[test]
+fn tail_call_test() {
+    let source = r#"
+        let count = fn (n, acc) => {
+            if (n == 0) {
+                acc
+            } else {
+                let next = n - 1;
+                count(next, acc + 1)
+            }
+        };
+        count(100000, 0)
+    "#;
+    let file = rinha::parser::parse_or_report("tail_call_test", source).expect("parse error");
+    let result = eval(Term::from(file.expression), &Env::new()).expect("error on evaluation");
+
+    assert_eq!(
+        format!("{:?}", result),
+        format!("{:?}", rinha_compiladores::val::Val::Int(100000))
+    );
+}
+
+#[test]
+fn closure_env_test() {
+    let source = r#"
+        let x = 1;
+        let get_x = fn () => { x };
+        let x = 2;
+        let add = fn (a) => { fn (b) => { a + b + x } };
+        (get_x(), add(10)(20))
+    "#;
+    let file = rinha::parser::parse_or_report("closure_env_test", source).expect("parse error");
+    let result = eval(Term::from(file.expression), &Env::new()).expect("error on evaluation");
+
+    assert_eq!(result.to_string(), "(1, 32)");
+}
+
+#[test]
+fn interrupt_test() {
+    let source =
+        "let forever = fn (n) => { if (n < 0) { n } else { forever(n + 1 - 1) } }; forever(0)";
+    let file = rinha::parser::parse_or_report("interrupt_test", source).expect("parse error");
+    let term = Term::from(file.expression);
+    let mut rt = Runtime::default();
+    let interrupt = rt.interrupt.clone();
+    let stopper = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        interrupt.interrupt();
+    });
+    let error = eval_with(term.clone(), &Env::new(), &mut rt).unwrap_err();
+    stopper.join().unwrap();
+    assert!(
+        matches!(error, RuntimeError::Interrupted { .. }),
+        "{error:?}"
+    );
+
+    // The flag stays set until reset.
+    let error = eval_with(term, &Env::new(), &mut rt).unwrap_err();
+    assert!(
+        matches!(error, RuntimeError::Interrupted { .. }),
+        "{error:?}"
+    );
+    rt.interrupt.reset();
+    let file =
+        rinha::parser::parse_or_report("interrupt_test", "let f = fn () => { 1 }; f()").unwrap();
+    let result = eval_with(Term::from(file.expression), &Env::new(), &mut rt).unwrap();
+    assert_eq!(result.to_string(), "1");
+}
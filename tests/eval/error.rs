@@ -0,0 +1,124 @@
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::core::eval;
+use rinha_compiladores::env::Env;
+use rinha_compiladores::error::RuntimeError;
+
+use super::both;
+
+/// Run a program on both backends, checking they fail the same way.
+fn fail(source: &str) -> RuntimeError {
+    both(source).expect_err("the program did not fail")
+}
+
+#[test]
+fn type_mismatch_test() {
+    let source = "let f = fn (x) => { x - true }; f(1)";
+    match fail(source) {
+        RuntimeError::TypeMismatch {
+            expected,
+            actual,
+            location,
+        } => {
+            assert_eq!((expected, actual), ("int", "bool"));
+            assert_eq!(&source[location.start..location.end], "x - true");
+        }
+        error => panic!("unexpected error {error:?}"),
+    }
+
+    let error = fail("if (1) { 2 } else { 3 }");
+    assert!(matches!(
+        error,
+        RuntimeError::TypeMismatch {
+            expected: "bool",
+            actual: "int",
+            ..
+        }
+    ));
+
+    let error = fail("let x = 1; x(2)");
+    assert!(matches!(
+        error,
+        RuntimeError::TypeMismatch {
+            expected: "closure",
+            ..
+        }
+    ));
+
+    let error = fail("first(1)");
+    assert!(matches!(
+        error,
+        RuntimeError::TypeMismatch {
+            expected: "tuple",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn arity_test() {
+    let error = fail("let f = fn (a, b) => { a + b }; f(1)");
+    assert!(matches!(
+        error,
+        RuntimeError::Arity {
+            expected: 2,
+            actual: 1,
+            ..
+        }
+    ));
+    // So are extra arguments.
+    let source = "let f = fn (a) => { a }; f(1, 2)";
+    let error = fail(source);
+    assert!(matches!(
+        error,
+        RuntimeError::Arity {
+            expected: 1,
+            actual: 2,
+            ..
+        }
+    ));
+    let location = error.location().expect("missing location");
+    assert_eq!(&source[location.start..location.end], "f(1, 2)");
+}
+
+#[test]
+fn division_by_zero_test() {
+    let source = "let n = 0; 10 % n";
+    let error = fail(source);
+    assert!(matches!(error, RuntimeError::DivisionByZero { .. }));
+    let location = error.location().expect("missing location");
+    assert_eq!(&source[location.start..location.end], "10 % n");
+}
+
+#[test]
+fn unbound_test() {
+    let file = rinha::parser::parse_or_report("error_test", "a + b").expect("parse error");
+    match eval(Term::from(file.expression), &Env::new()) {
+        Err(RuntimeError::Unbound { variables }) => assert_eq!(variables.len(), 2),
+        result => panic!("unexpected result {result:?}"),
+    }
+}
+
+#[test]
+fn string_too_short_test() {
+    let source = r#"let s = "a"; (first(s), second(s))"#;
+    match fail(source) {
+        RuntimeError::StringTooShort {
+            position,
+            length,
+            location,
+        } => {
+            assert_eq!((position, length), ("second", 1));
+            assert_eq!(&source[location.start..location.end], "second(s)");
+        }
+        error => panic!("unexpected error {error:?}"),
+    }
+    let error = fail(r#"first("")"#);
+    assert!(matches!(
+        error,
+        RuntimeError::StringTooShort {
+            position: "first",
+            length: 0,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::codegen::compile;
+use rinha_compiladores::core::eval_with;
+use rinha_compiladores::env::Env;
+use rinha_compiladores::error::RuntimeError;
+use rinha_compiladores::interpreter::Interpreter;
+use rinha_compiladores::limits::{Limit, Limits, DEFAULT_DEPTH};
+use rinha_compiladores::runtime::Runtime;
+use rinha_compiladores::val::Val;
+use rinha_compiladores::vm::Vm;
+
+const FOREVER: &str = "let f = fn () => { f() };\nf()";
+const DEEP: &str = "let f = fn (n) => { 1 + f(n) };\nf(0)";
+const GREEDY: &str = "let f = fn (s) => { f((s + s, 0)) };\nf(\"ab\")";
+const FIB: &str = r#"
+    let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+    fib(15)
+"#;
+
+fn parse(source: &str) -> Term {
+    let file = rinha::parser::parse_or_report("limits_test", source).expect("parse error");
+    Term::from(file.expression)
+}
+
+/// Run a program on both backends, returning the limit it exceeded on each
+/// and where.
+fn exceeded(source: &str, limits: Limits) -> [(Limit, usize); 2] {
+    let mut rt = Runtime {
+        limits: limits.clone(),
+        ..Default::default()
+    };
+    let tree = eval_with(parse(source), &Env::new(), &mut rt);
+    let vm = Vm::default()
+        .with_limits(limits)
+        .run(compile(&parse(source)));
+    [tree, vm].map(|result| match result {
+        Err(RuntimeError::LimitExceeded { limit, location }) => (limit, location.start),
+        result => panic!("no limit exceeded: {result:?}"),
+    })
+}
+
+#[test]
+fn calls_test() {
+    let limits = Limits {
+        calls: Some(100),
+        ..Default::default()
+    };
+    assert_eq!(exceeded(FOREVER, limits), [(Limit::Calls(100), 19); 2]);
+
+    // fib(15) makes 1973 calls, the top-level one included.
+    let mut rt = Runtime::default();
+    eval_with(parse(FIB), &Env::new(), &mut rt).expect("error");
+    assert_eq!(rt.usage.calls, 1973);
+    rt.limits.calls = Some(1973);
+    eval_with(parse(FIB), &Env::new(), &mut rt).expect("error");
+    rt.limits.calls = Some(1972);
+    assert!(eval_with(parse(FIB), &Env::new(), &mut rt).is_err());
+}
+
+#[test]
+fn depth_test() {
+    let limits = Limits {
+        depth: Some(100),
+        ..Default::default()
+    };
+    assert_eq!(exceeded(DEEP, limits.clone()), [(Limit::Depth(100), 24); 2]);
+
+    // Both backends count the calls in progress, the program included: the
+    // top-level tail call replaces it, then each level makes one more.
+    let countdown = |n: i32| {
+        format!("let f = fn (n) => {{ if (n == 0) {{ 0 }} else {{ 1 + f(n - 1) }} }};\nf({n})")
+    };
+    let mut rt = Runtime {
+        limits: limits.clone(),
+        ..Default::default()
+    };
+    let tree = eval_with(parse(&countdown(99)), &Env::new(), &mut rt);
+    assert_eq!(tree.expect("error on the tree walker").to_string(), "99");
+    let vm = Vm::default()
+        .with_limits(limits.clone())
+        .run(compile(&parse(&countdown(99))));
+    assert_eq!(vm.expect("error on the VM").to_string(), "99");
+    let [(tree, _), (vm, _)] = exceeded(&countdown(100), limits);
+    assert_eq!([tree, vm], [Limit::Depth(100); 2]);
+
+    // Tail calls do not nest.
+    let limits = Limits {
+        depth: Some(10),
+        calls: Some(10_000),
+        ..Default::default()
+    };
+    assert_eq!(exceeded(FOREVER, limits), [(Limit::Calls(10_000), 19); 2]);
+
+    // Deep recursions stop at the default depth instead of overflowing the
+    // stack.
+    let depth = Limit::Depth(DEFAULT_DEPTH);
+    assert_eq!(exceeded(DEEP, Limits::default()), [(depth, 24); 2]);
+}
+
+#[test]
+fn memory_test() {
+    let limits = Limits {
+        memory: Some(1 << 20),
+        ..Default::default()
+    };
+    let [(tree, _), (vm, _)] = exceeded(GREEDY, limits);
+    assert_eq!(tree, Limit::Memory(1 << 20));
+    assert_eq!(vm, Limit::Memory(1 << 20));
+}
+
+#[test]
+fn timeout_test() {
+    let limits = Limits {
+        timeout: Some(Duration::from_millis(50)),
+        ..Default::default()
+    };
+    let timeout = Limit::Timeout(Duration::from_millis(50));
+    assert_eq!(exceeded(FOREVER, limits), [(timeout, 19); 2]);
+}
+
+#[test]
+fn interpreter_limits_test() {
+    let mut interpreter = Interpreter::builder()
+        .limits(Limits {
+            calls: Some(2000),
+            ..Default::default()
+        })
+        .build();
+    // Each program gets the whole budget.
+    for _ in 0..3 {
+        assert_eq!(interpreter.eval(FIB).expect("error").to_string(), "610");
+    }
+    let error = interpreter.eval(FOREVER).unwrap_err();
+    assert!(matches!(
+        error.runtime_error(),
+        Some(RuntimeError::LimitExceeded {
+            limit: Limit::Calls(2000),
+            ..
+        })
+    ));
+
+    let fib = interpreter.get("fib").expect("unbound");
+    let value: i32 = interpreter.call(&fib, vec![Val::Int(15)]).expect("error");
+    assert_eq!(value, 610);
+    let error = interpreter
+        .call::<Val>(&fib, vec![Val::Int(20)])
+        .unwrap_err();
+    assert!(matches!(
+        error.runtime_error(),
+        Some(RuntimeError::LimitExceeded { .. })
+    ));
+}
@@ -0,0 +1,100 @@
+use rinha_compiladores::error::RuntimeError;
+
+use super::{both_with, Settings};
+
+/// Run a program on both backends, returning its value and what it printed
+/// as `value; output`.
+fn run(source: &str) -> Result<String, RuntimeError> {
+    let (value, output) = both_with(source, Settings::default())?;
+    Ok(format!("{value}; {}", output.replace('\n', " ")))
+}
+
+#[test]
+fn short_circuit_test() {
+    assert_eq!(run("print(false) && print(true)").unwrap(), "false; false ");
+    assert_eq!(
+        run("print(true) && print(false)").unwrap(),
+        "false; true false "
+    );
+    assert_eq!(run("print(true) || print(false)").unwrap(), "true; true ");
+    assert_eq!(
+        run("print(false) || print(true)").unwrap(),
+        "true; false true "
+    );
+    // Operators are right associative, `a || b && c` is `a || (b && c)`.
+    assert_eq!(
+        run("(print(1) == 1) || (print(2) == 2) && (print(3) == 3)").unwrap(),
+        "true; 1 "
+    );
+    assert_eq!(
+        run("(print(1) == 2) || (print(2) == 2) && (print(3) == 3)").unwrap(),
+        "true; 1 2 3 "
+    );
+}
+
+#[test]
+fn guard_test() {
+    assert_eq!(
+        run("let n = 0; (n > 0) && ((10 / n) > 1)").unwrap(),
+        "false; "
+    );
+    assert_eq!(
+        run("let n = 0; (n == 0) || ((10 / n) > 1)").unwrap(),
+        "true; "
+    );
+    // Recursion ending on the left operand.
+    let source = "let all = fn (n) => { (n == 0) || ((n > 0) && all(n - 1)) }; all(100)";
+    assert_eq!(run(source).unwrap(), "true; ");
+}
+
+#[test]
+fn operand_type_test() {
+    let source = "1 && print(true)";
+    let error = run(source).unwrap_err();
+    assert!(matches!(
+        error,
+        RuntimeError::TypeMismatch {
+            expected: "bool",
+            actual: "int",
+            ..
+        }
+    ));
+    let location = error.location().expect("missing location");
+    assert_eq!(&source[location.start..location.end], source);
+
+    // The right operand is only checked when it is evaluated.
+    assert_eq!(run("true || \"a\"").unwrap(), "true; ");
+    assert!(matches!(
+        run("true && \"a\"").unwrap_err(),
+        RuntimeError::TypeMismatch {
+            expected: "bool",
+            actual: "str",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn tail_operand_test() {
+    // Calls in the right operand are tail calls, so this does not nest.
+    let source = r#"
+        let even = fn (n) => { (n == 0) || odd(n - 1) };
+        let odd = fn (n) => { (n != 0) && even(n - 1) };
+        (even(100000), odd(100001))
+    "#;
+    assert_eq!(run(source).unwrap(), "(true, true); ");
+
+    // Their value is still checked, at the innermost operator.
+    let source = "let f = fn () => { 1 }; let g = fn () => { true && f() }; false || g()";
+    let error = run(source).unwrap_err();
+    assert!(matches!(
+        error,
+        RuntimeError::TypeMismatch {
+            expected: "bool",
+            actual: "int",
+            ..
+        }
+    ));
+    let location = error.location().expect("missing location");
+    assert_eq!(&source[location.start..location.end], "true && f()");
+}
@@ -0,0 +1,136 @@
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::core::eval_with;
+use rinha_compiladores::env::Env;
+use rinha_compiladores::memo::Memo;
+use rinha_compiladores::runtime::Runtime;
+
+fn run(source: &str, rt: &mut Runtime) -> String {
+    let file = rinha::parser::parse_or_report("memo_test", source).expect("parse error");
+    let result =
+        eval_with(Term::from(file.expression), &Env::new(), rt).expect("error on evaluation");
+    format!("{:?}", result)
+}
+
+#[test]
+fn memo_pure_test() {
+    let mut rt = Runtime {
+        memo: Memo::new(true),
+        ..Default::default()
+    };
+    let source = r#"
+        let fib = fn (n) => {
+            if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }
+        };
+        fib(40)
+    "#;
+
+    assert_eq!(run(source, &mut rt), "Int(102334155)");
+    assert_eq!(rt.memo.misses, 41);
+    assert_eq!(rt.memo.hits, 38);
+}
+
+#[test]
+fn memo_impure_test() {
+    let mut rt = Runtime {
+        memo: Memo::new(true),
+        ..Default::default()
+    };
+    let source = r#"
+        let log = fn (n) => { print(n) };
+        let twice = fn (n) => { log(n) + log(n) };
+        let apply = fn (f, n) => { f(n) };
+        let double = fn (n) => { n * 2 };
+        (twice(1) + twice(1), apply(double, 2) + apply(double, 2))
+    "#;
+
+    assert_eq!(
+        run(source, &mut rt),
+        "Tuple(Tuple { f: Int(4), s: Int(8) })"
+    );
+    // Only `double` is pure: `twice` prints through `log` and `apply` calls
+    // a parameter.
+    assert_eq!(rt.memo.len(), 1);
+    assert_eq!(rt.memo.hits, 1);
+}
+
+#[test]
+fn memo_disabled_test() {
+    let mut rt = Runtime {
+        memo: Memo::new(false),
+        ..Default::default()
+    };
+    let source = r#"
+        let square = fn (n) => { n * n };
+        square(3) + square(3)
+    "#;
+
+    assert_eq!(run(source, &mut rt), "Int(18)");
+    assert_eq!(rt.memo.hits + rt.memo.misses, 0);
+}
+
+#[test]
+fn memo_closure_argument_test() {
+    let mut rt = Runtime {
+        memo: Memo::new(true),
+        ..Default::default()
+    };
+    let source = r#"
+        let tag = fn (f, n) => { (n, n * 2) };
+        let one = fn () => { 1 };
+        let two = fn () => { 2 };
+        (tag(one, 1), (tag(one, 1), tag(two, 1)))
+    "#;
+
+    assert_eq!(
+        run(source, &mut rt).matches("Int(2)").count(),
+        3,
+        "every call returns (1, 2)"
+    );
+    // Closures are keys by identity, so only the second call with `one` hits.
+    assert_eq!(rt.memo.hits, 1);
+    assert_eq!(rt.memo.len(), 2);
+}
+
+#[test]
+fn memo_per_evaluation_test() {
+    let mut rt = Runtime {
+        memo: Memo::new(true),
+        ..Default::default()
+    };
+    let source = r#"
+        let fib = fn (n) => {
+            if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }
+        };
+        fib(20)
+    "#;
+
+    // Each evaluation starts with an empty cache, so repeating one does not
+    // grow it.
+    for _ in 0..3 {
+        assert_eq!(run(source, &mut rt), "Int(6765)");
+        assert_eq!(rt.memo.len(), 21);
+        assert_eq!(rt.memo.misses, 21);
+    }
+    run("1", &mut rt);
+    assert!(rt.memo.is_empty());
+}
+
+#[test]
+fn memo_loop_test() {
+    let mut rt = Runtime {
+        memo: Memo::new(true),
+        ..Default::default()
+    };
+    let source = r#"
+        let count = fn (n, acc) => {
+            if (n == 0) { acc } else { count(n - 1, acc + 1) }
+        };
+        let twice = fn (n) => { count(n, 0) + count(n, 0) };
+        twice(10000)
+    "#;
+
+    // Only `twice` is memoized, every iteration of the loop runs.
+    assert_eq!(run(source, &mut rt), "Int(20000)");
+    assert_eq!(rt.memo.len(), 1);
+    assert_eq!(rt.memo.misses, 1);
+}
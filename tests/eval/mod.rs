@@ -1 +1,70 @@
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::codegen::compile;
+use rinha_compiladores::core::eval_with;
+use rinha_compiladores::env::Env;
+use rinha_compiladores::error::RuntimeError;
+use rinha_compiladores::eval_binary::Arithmetic;
+use rinha_compiladores::eval_call::Application;
+use rinha_compiladores::output::Buffer;
+use rinha_compiladores::runtime::Runtime;
+use rinha_compiladores::vm::Vm;
+
+mod application;
+mod arithmetic;
+mod compare;
+mod convert;
 mod core;
+mod error;
+mod limits;
+mod logic;
+mod memo;
+mod output;
+mod recursion;
+mod resolve;
+mod scope;
+
+/// Semantics both backends run a program with in [`both_with`].
+#[derive(Debug, Clone, Copy, Default)]
+struct Settings {
+    arithmetic: Arithmetic,
+    application: Application,
+}
+
+/// Run a program on both backends, checking they agree on its value or on
+/// its error and where it happened, and on what it printed. Returns the
+/// value with the output.
+fn both_with(source: &str, settings: Settings) -> Result<(String, String), RuntimeError> {
+    let file = rinha::parser::parse_or_report("eval_test", source).expect("parse error");
+    let term = Term::from(file.expression);
+    let vm_output = Buffer::default();
+    let vm = Vm::new(Box::new(vm_output.clone()))
+        .with_arithmetic(settings.arithmetic)
+        .with_application(settings.application)
+        .run(compile(&term));
+    let tree_output = Buffer::default();
+    let mut rt = Runtime {
+        output: Box::new(tree_output.clone()),
+        arithmetic: settings.arithmetic,
+        application: settings.application,
+        ..Default::default()
+    };
+    let tree = eval_with(term, &Env::new(), &mut rt);
+    assert_eq!(tree_output.contents(), vm_output.contents(), "{source}");
+    match (tree, vm) {
+        (Ok(tree), Ok(vm)) => {
+            assert_eq!(tree.to_string(), vm.to_string(), "{source}");
+            Ok((tree.to_string(), tree_output.contents()))
+        }
+        (Err(tree), Err(vm)) => {
+            assert_eq!(tree.to_string(), vm.to_string(), "{source}");
+            assert_eq!(tree.location(), vm.location(), "{source}");
+            Err(tree)
+        }
+        (tree, vm) => panic!("the backends disagree on {source}: {tree:?} and {vm:?}"),
+    }
+}
+
+/// [`both_with`] the default semantics, returning the value only.
+fn both(source: &str) -> Result<String, RuntimeError> {
+    both_with(source, Settings::default()).map(|(value, _)| value)
+}
@@ -0,0 +1,134 @@
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::codegen::compile;
+use rinha_compiladores::core::eval_with;
+use rinha_compiladores::env::Env;
+use rinha_compiladores::error::RuntimeError;
+use rinha_compiladores::output::{Buffer, Callback, Output, Writer};
+use rinha_compiladores::runtime::Runtime;
+use rinha_compiladores::val::Val;
+use rinha_compiladores::vm::Vm;
+
+fn parse(source: &str) -> Term {
+    let file = rinha::parser::parse_or_report("output_test", source).expect("parse error");
+    Term::from(file.expression)
+}
+
+fn run_with(source: &str, output: impl Output + 'static) -> Result<Val, RuntimeError> {
+    let mut rt = Runtime {
+        output: Box::new(output),
+        ..Default::default()
+    };
+    eval_with(parse(source), &Env::new(), &mut rt)
+}
+
+/// Output keeping track of what it was asked to do.
+#[derive(Clone, Default)]
+struct Recorder {
+    lines: Rc<RefCell<Vec<String>>>,
+    flushes: Rc<RefCell<usize>>,
+    fail: bool,
+}
+
+impl Output for Recorder {
+    fn print(&mut self, val: &Val) -> io::Result<()> {
+        if self.fail {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+        }
+        self.lines.borrow_mut().push(val.to_string());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        *self.flushes.borrow_mut() += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn buffer_test() {
+    let buffer = Buffer::default();
+    let source = r#"
+        let f = fn (n) => { if (n == 0) { 0 } else { let _ = print(n); f(n - 1) } };
+        let _ = f(3);
+        print(("done", fn () => { 1 }))
+    "#;
+    run_with(source, buffer.clone()).expect("error");
+    assert_eq!(buffer.take(), "3\n2\n1\n(done, <#closure>)\n");
+    assert_eq!(buffer.contents(), "");
+}
+
+#[test]
+fn writer_test() {
+    let bytes = Rc::new(RefCell::new(Vec::new()));
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+    impl io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    run_with("print(1 + 1)", Writer(Shared(bytes.clone()))).expect("error");
+    assert_eq!(bytes.borrow().as_slice(), b"2\n");
+}
+
+#[test]
+fn callback_test() {
+    let printed = Rc::new(RefCell::new(Vec::new()));
+    let sink = printed.clone();
+    let callback = Callback(move |val: &Val| sink.borrow_mut().push(val.clone()));
+    run_with("let _ = print(1); print(true)", callback).expect("error");
+    let printed = printed.borrow();
+    assert!(matches!(printed.as_slice(), [Val::Int(1), Val::Bool(true)]));
+}
+
+#[test]
+fn flushed_on_error_test() {
+    let recorder = Recorder::default();
+    let error = run_with("let _ = print(1); 1 % 0", recorder.clone()).unwrap_err();
+    assert!(matches!(error, RuntimeError::DivisionByZero { .. }));
+    assert_eq!(*recorder.lines.borrow(), ["1"]);
+    assert_eq!(*recorder.flushes.borrow(), 1);
+
+    run_with("print(2)", recorder.clone()).expect("error");
+    assert_eq!(*recorder.flushes.borrow(), 2);
+}
+
+#[test]
+fn print_failure_test() {
+    let recorder = Recorder {
+        fail: true,
+        ..Default::default()
+    };
+    let error = run_with("let x = 1;\nprint(x)", recorder).unwrap_err();
+    match error {
+        RuntimeError::Output { message, location } => {
+            assert_eq!(message, "closed");
+            assert_eq!(location.start, 11);
+        }
+        error => panic!("unexpected error {error:?}"),
+    }
+}
+
+#[test]
+fn vm_output_test() {
+    let recorder = Recorder::default();
+    let proto = compile(&parse("let _ = print((1, 2)); print(3) + 1 % 0"));
+    let error = Vm::new(Box::new(recorder.clone())).run(proto).unwrap_err();
+    assert!(matches!(error, RuntimeError::DivisionByZero { .. }));
+    assert_eq!(*recorder.lines.borrow(), ["(1, 2)", "3"]);
+    assert_eq!(*recorder.flushes.borrow(), 1);
+
+    let failing = Recorder {
+        fail: true,
+        ..Default::default()
+    };
+    let proto = compile(&parse("print(1)"));
+    let error = Vm::new(Box::new(failing)).run(proto).unwrap_err();
+    assert!(matches!(error, RuntimeError::Output { .. }));
+}
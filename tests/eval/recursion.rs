@@ -0,0 +1,89 @@
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::codegen::compile;
+use rinha_compiladores::core::{eval, eval_with};
+use rinha_compiladores::env::Env;
+use rinha_compiladores::memo::Memo;
+use rinha_compiladores::runtime::Runtime;
+use rinha_compiladores::vm::run;
+
+use super::both;
+
+const EVEN_ODD: &str = r#"
+    let even = fn (n) => { if (n == 0) { true } else { odd(n - 1) } };
+    let odd = fn (n) => { if (n == 0) { false } else { even(n - 1) } };
+"#;
+
+fn parse(source: &str) -> Term {
+    let file = rinha::parser::parse_or_report("recursion_test", source).expect("parse error");
+    Term::from(file.expression)
+}
+
+fn value(source: &str) -> String {
+    both(source).expect("error")
+}
+
+#[test]
+fn mutual_recursion_test() {
+    assert_eq!(
+        value(&format!("{EVEN_ODD} (even(10), odd(7))")),
+        "(true, true)"
+    );
+    // Tail calls between the functions of a group do not grow the stack.
+    assert_eq!(value(&format!("{EVEN_ODD} even(100000)")), "true");
+}
+
+#[test]
+fn nested_group_test() {
+    let source = r#"
+        let count = fn (limit) => {
+            let ping = fn (n, acc) => { if (n == limit) { acc } else { pong(n + 1, acc + 1) } };
+            let pong = fn (n, acc) => { if (n == limit) { acc } else { ping(n + 1, acc + 10) } };
+            let three = fn () => { ping(0, 0) };
+            three()
+        };
+        (count(3), count(4))
+    "#;
+    assert_eq!(value(source), "(12, 22)");
+}
+
+#[test]
+fn group_returned_test() {
+    // The closures keep seeing each other after their frame is gone.
+    let source = format!(
+        r#"
+        let make = fn () => {{ {EVEN_ODD} (even, odd) }};
+        let pair = make();
+        (first(pair)(3), second(pair)(3))
+    "#
+    );
+    assert_eq!(value(&source), "(false, true)");
+}
+
+#[test]
+fn group_boundaries_test() {
+    // A `let` of another value ends the group.
+    let source = "let f = fn () => { g() }; let x = 1; let g = fn () => { x }; f()";
+    assert!(eval(parse(source), &Env::new()).is_err());
+    assert!(run(compile(&parse(source))).is_err());
+    // So does redefining a name of the group, which the functions before
+    // keep seeing with its first value.
+    let source = r#"
+        let f = fn () => { 1 };
+        let g = fn () => { f() };
+        let f = fn () => { g() + 1 };
+        (f(), g())
+    "#;
+    assert_eq!(value(source), "(2, 1)");
+}
+
+#[test]
+fn memo_group_test() {
+    let mut rt = Runtime {
+        memo: Memo::new(true),
+        ..Default::default()
+    };
+    let source = format!("{EVEN_ODD} (even(20), odd(21))");
+    let result = eval_with(parse(&source), &Env::new(), &mut rt).expect("error on evaluation");
+    assert_eq!(result.to_string(), "(true, true)");
+    assert!(rt.memo.hits > 0);
+}
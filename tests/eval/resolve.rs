@@ -0,0 +1,48 @@
+use rinha_compiladores::ast::Term;
+use rinha_compiladores::core::eval;
+use rinha_compiladores::env::Env;
+use rinha_compiladores::resolve::resolve;
+use rinha_compiladores::val::Val;
+
+fn parse(source: &str) -> Term {
+    let file = rinha::parser::parse_or_report("resolve_test", source).expect("parse error");
+    Term::from(file.expression)
+}
+
+#[test]
+fn resolve_unbound_test() {
+    let term = parse("let f = fn (a) => { a + b }; let _ = print(c); f(d)");
+    let errors = resolve(&term, &[]).unwrap_err();
+
+    let names: Vec<_> = errors.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["b", "c", "d"]);
+    assert!(errors[0].to_string().starts_with("unbound variable `b`"));
+}
+
+#[test]
+fn resolve_scope_test() {
+    // A `let` only sees itself when its value is a function.
+    let errors = resolve(&parse("let x = x + 1; x"), &[]).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name, "x");
+
+    // Bindings are not visible after the `let` they belong to.
+    let errors = resolve(&parse("let f = fn () => { let y = 1; y }; y"), &[]).unwrap_err();
+    assert_eq!(errors.len(), 1);
+
+    let script = resolve(&parse("let a = 1; let b = 2; let a = 3; a + b"), &[]).unwrap();
+    assert_eq!(script.slots, 3);
+}
+
+#[test]
+fn resolve_globals_test() {
+    let globals = ["x".to_string()];
+    assert!(resolve(&parse("x + 1"), &globals).is_ok());
+
+    let env = Env::new().bind("x", Val::Int(41));
+    let result = eval(parse("x + 1"), &env).expect("error on evaluation");
+    assert_eq!(result.to_string(), "42");
+
+    let error = eval(parse("let _ = print(1); y"), &Env::new()).unwrap_err();
+    assert!(error.to_string().contains("`y`"));
+}
@@ -0,0 +1,111 @@
+use super::both;
+
+fn value(source: &str) -> String {
+    both(source).expect("error")
+}
+
+#[test]
+fn binary_test() {
+    assert_eq!(value("let x = 1; (let x = 10; x) + x"), "11");
+    assert_eq!(value("let x = 1; x + (let x = 10; x)"), "11");
+    assert_eq!(value("let x = 1; (let x = 10; x) + (let y = x; y)"), "11");
+    assert_eq!(
+        value("let x = 1; let y = (let x = 5; x * 2) + x; (x, y)"),
+        "(1, 11)"
+    );
+}
+
+#[test]
+fn call_argument_test() {
+    let source = r#"
+        let x = 1;
+        let pair = fn (a, b) => { (a, b) };
+        let f = fn (n) => { n + x };
+        (pair((let x = 100; f(x)), x), f(0))
+    "#;
+    assert_eq!(value(source), "((101, 1), 1)");
+    assert_eq!(
+        value("let f = fn (a) => { a }; let a = 2; (f(let a = 3; a), a)"),
+        "(3, 2)"
+    );
+}
+
+#[test]
+fn tuple_test() {
+    assert_eq!(value("let x = 1; ((let x = 2; x), x)"), "(2, 1)");
+    assert_eq!(value("let x = 1; (x, (let x = 2; x))"), "(1, 2)");
+    assert_eq!(
+        value("let x = 1; ((let x = 2; (x, let x = 3; x)), x)"),
+        "((2, 3), 1)"
+    );
+}
+
+#[test]
+fn branch_test() {
+    assert_eq!(
+        value("let x = 1; let y = if (let x = 2; x == 2) { x } else { 0 }; (x, y)"),
+        "(1, 1)"
+    );
+    assert_eq!(
+        value("let x = 1; let y = if (true) { let x = 2; x } else { x }; (x, y)"),
+        "(1, 2)"
+    );
+    assert_eq!(
+        value("let x = 1; let y = if (false) { let x = 2; x } else { let x = x + 5; x }; (x, y)"),
+        "(1, 6)"
+    );
+}
+
+#[test]
+fn closure_test() {
+    // Closures see the binding visible where they are defined, not the
+    // ones of the place they are called from.
+    let source = r#"
+        let x = 1;
+        let get = fn () => { x };
+        let x = 2;
+        let call = fn (f) => { let x = 3; f() };
+        (get(), (call(get), x))
+    "#;
+    assert_eq!(value(source), "(1, (1, 2))");
+    let source = r#"
+        let make = fn (x) => { fn () => { x } };
+        let one = make(1);
+        let two = make(2);
+        (one(), two())
+    "#;
+    assert_eq!(value(source), "(1, 2)");
+}
+
+#[test]
+fn parameter_test() {
+    // Lets of a body shadow parameters for the rest of the body only.
+    let source = r#"
+        let f = fn (x) => { ((let x = x * 10; x), x) };
+        let x = 7;
+        (f(1), x)
+    "#;
+    assert_eq!(value(source), "((10, 1), 7)");
+}
+
+#[test]
+fn later_function_test() {
+    // A function defined later does not replace the binding an earlier one
+    // sees, even when both are functions next to each other.
+    let source = "let g = 5; let f = fn () => { g }; let g = fn () => { 1 }; print(f())";
+    assert_eq!(value(source), "5");
+    let source = r#"
+        let g = fn () => { 5 };
+        let f = fn () => { g() };
+        let g = fn () => { f() + 1 };
+        (f(), g())
+    "#;
+    assert_eq!(value(source), "(5, 6)");
+}
+
+#[test]
+fn deep_let_test() {
+    // Resolving recurses once per `let`, on a stack grown as needed.
+    let lets: String = (0..20_000).map(|i| format!("let x{i} = {i};\n")).collect();
+    assert_eq!(value(&format!("{lets}x0 + x19999")), "19999");
+}
@@ -1,2 +1,7 @@
+mod ast;
+mod check;
+mod cli;
 mod compiler;
 mod eval;
+mod pretty;
+mod repl;
@@ -0,0 +1 @@
+mod print;
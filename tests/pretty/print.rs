@@ -0,0 +1,86 @@
+use rinha_compiladores::ast::{File, Term};
+use rinha_compiladores::pretty::{has_comments, print_file, print_term};
+
+fn parse(source: &str) -> File {
+    File::from(rinha::parser::parse_or_report("print_test", source).expect("parse error"))
+}
+
+/// Format a program, checking that the result parses back to the same AST.
+fn format(source: &str) -> String {
+    let file = parse(source);
+    let formatted = print_file(&file);
+    let reparsed = parse(&formatted);
+    // Locations are left out of the debug output of the AST.
+    assert_eq!(
+        format!("{:?}", reparsed.expression),
+        format!("{:?}", file.expression),
+        "{formatted}"
+    );
+    formatted
+}
+
+#[test]
+fn precedence_test() {
+    assert_eq!(format("(1 + 2) * 3"), "(1 + 2) * 3\n");
+    assert_eq!(format("1 + (2 * 3)"), "1 + 2 * 3\n");
+    assert_eq!(format("(1 - 2) - 3"), "(1 - 2) - 3\n");
+    assert_eq!(format("1 - (2 - 3)"), "1 - 2 - 3\n");
+    assert_eq!(format("(a == b) == c"), "(a == b) == c\n");
+    assert_eq!(format("a < b + 1 && c"), "a < b + 1 && c\n");
+    assert_eq!(format("(fn (x) => x)(1)"), "(fn (x) => { x })(1)\n");
+    assert_eq!(format("f(1)(2)"), "f(1)(2)\n");
+    assert_eq!(format("first((1, 2)) + 1"), "first((1, 2)) + 1\n");
+}
+
+#[test]
+fn layout_test() {
+    assert_eq!(
+        format("let x = 1; if (x == 1) { let y = 2; y } else { fn () => x }"),
+        "let x = 1;\nif (x == 1) {\n  let y = 2;\n  y\n} else {\n  fn () => { x }\n}\n"
+    );
+    assert_eq!(
+        format("let f = fn (a) => { let b = a; b }; (let c = 1; c, f)"),
+        "let f = fn (a) => {\n  let b = a;\n  b\n};\n({\n  let c = 1;\n  c\n}, f)\n"
+    );
+}
+
+#[test]
+fn negative_int_test() {
+    let term = Term::Int(rinha_compiladores::ast::Int {
+        value: -5,
+        location: Default::default(),
+    });
+    assert_eq!(print_term(&term), "(0 - 5)");
+}
+
+#[test]
+fn round_trip_test() {
+    let source = std::fs::read_to_string("examples/source.rinha").expect("missing example");
+    let formatted = format(&source);
+    // Formatting is idempotent.
+    assert_eq!(format(&formatted), formatted);
+}
+
+#[test]
+fn has_comments_test() {
+    assert!(has_comments("1 // one"));
+    assert!(has_comments("/* one */ 1"));
+    assert!(!has_comments(r#""http://example.com" + 1"#));
+    assert!(!has_comments("4 / 2"));
+    assert!(!has_comments(r#""a \" // b""#));
+    assert!(has_comments(r#""a \\" // b"#));
+}
+
+#[test]
+fn nested_functions_test() {
+    // Each body is rendered once, however deep the functions nest.
+    let depth = 40;
+    let source = format!(
+        "{}let x = 1; x{}",
+        "fn () => { ".repeat(depth),
+        " }".repeat(depth)
+    );
+    let formatted = format(&source);
+    assert_eq!(formatted.lines().count(), 2 * depth + 2);
+    assert!(formatted.contains(&format!("\n{}x\n", "  ".repeat(depth))));
+}
@@ -0,0 +1,28 @@
+use rinha_compiladores::repl::Command;
+
+#[test]
+fn parse_test() {
+    assert_eq!(
+        Command::parse(":load  a.rinha "),
+        Some(Command::Load("a.rinha"))
+    );
+    assert_eq!(Command::parse(":reload"), Some(Command::Reload));
+    assert_eq!(Command::parse(":ast 1 + 2"), Some(Command::Ast("1 + 2")));
+    assert_eq!(Command::parse(":t x"), Some(Command::Type("x")));
+    assert_eq!(Command::parse(":env"), Some(Command::Env));
+    assert_eq!(Command::parse(":time f(1)"), Some(Command::Time("f(1)")));
+    assert_eq!(Command::parse(":help"), Some(Command::Help));
+    assert_eq!(Command::parse("let x = 1; x"), None);
+}
+
+#[test]
+fn invalid_test() {
+    assert_eq!(
+        Command::parse(":frobnicate"),
+        Some(Command::Invalid("unknown command :frobnicate".to_string()))
+    );
+    assert_eq!(
+        Command::parse(":load"),
+        Some(Command::Invalid(":load needs a file".to_string()))
+    );
+}
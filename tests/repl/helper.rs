@@ -0,0 +1,60 @@
+use rinha_compiladores::repl::{incomplete, RinhaHelper};
+use rustyline::highlight::Highlighter;
+
+#[test]
+fn incomplete_test() {
+    assert!(incomplete("let f = fn (x) => {"));
+    assert!(incomplete("f(1, (2"));
+    assert!(incomplete("let s = \"multi"));
+    assert!(incomplete("1 /* comment"));
+    assert!(!incomplete("let f = fn (x) => { x };"));
+    // Brackets in strings and comments do not count.
+    assert!(!incomplete("\"{(\" // {"));
+    // Extra closing brackets are for the parser to report.
+    assert!(!incomplete("f(1))"));
+}
+
+#[test]
+fn highlight_test() {
+    let helper = RinhaHelper::default();
+    let highlighted = helper.highlight("let x = print(\"a\") + 1; // c", 0);
+    let plain = |s: &str| {
+        let mut out = String::new();
+        let mut escape = false;
+        for c in s.chars() {
+            match c {
+                '\x1b' => escape = true,
+                'm' if escape => escape = false,
+                c if !escape => out.push(c),
+                _ => {}
+            }
+        }
+        out
+    };
+    assert_eq!(plain(&highlighted), "let x = print(\"a\") + 1; // c");
+    assert!(highlighted.starts_with("\x1b[35mlet\x1b[0m x \x1b[36m=\x1b[0m \x1b[34mprint\x1b[0m("));
+    assert!(highlighted.contains("\x1b[32m\"a\"\x1b[0m"));
+    assert!(highlighted.contains("\x1b[33m1\x1b[0m"));
+    assert!(highlighted.ends_with("\x1b[90m// c\x1b[0m"));
+}
+
+#[test]
+fn candidates_test() {
+    let helper = RinhaHelper {
+        names: vec!["fib".to_string(), "first_of".to_string()],
+    };
+    assert_eq!(
+        helper.candidates("1 + fi", 6),
+        (
+            4,
+            vec![
+                "fib".to_string(),
+                "first".to_string(),
+                "first_of".to_string()
+            ]
+        )
+    );
+    assert_eq!(helper.candidates("pr", 2), (0, vec!["print".to_string()]));
+    assert_eq!(helper.candidates(":lo", 3), (0, vec![":load".to_string()]));
+    assert_eq!(helper.candidates("f(", 2), (2, vec![]));
+}
@@ -0,0 +1,3 @@
+mod command;
+mod helper;
+mod session;
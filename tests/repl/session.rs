@@ -0,0 +1,188 @@
+use rinha_compiladores::ast::File;
+use rinha_compiladores::error::RuntimeError;
+use rinha_compiladores::repl::{Session, SessionError};
+
+fn value(session: &mut Session, source: &str) -> String {
+    let outcome = session.eval(source).expect("error");
+    outcome.value.expect("no value").to_string()
+}
+
+#[test]
+fn bindings_persist_test() {
+    let mut session = Session::new();
+    let outcome = session.eval("let x = 1; let y = x + 1; y").unwrap();
+    assert_eq!(outcome.defined, ["x", "y"]);
+    assert_eq!(value(&mut session, "x + y"), "3");
+    // Later bindings shadow earlier ones.
+    session.eval("let x = 10;").unwrap();
+    assert_eq!(value(&mut session, "x + y"), "12");
+    assert_eq!(session.names(), ["x", "y"]);
+}
+
+#[test]
+fn nested_lets_stay_local_test() {
+    let mut session = Session::new();
+    let outcome = session.eval("let x = 1; (let y = 2; y) + x").unwrap();
+    assert_eq!(outcome.defined, ["x"]);
+    assert_eq!(session.names(), ["x"]);
+    assert!(matches!(
+        session.eval("y"),
+        Err(SessionError::Runtime(RuntimeError::Unbound { .. }))
+    ));
+}
+
+#[test]
+fn trailing_let_test() {
+    let mut session = Session::new();
+    let outcome = session.eval("let a = 1; let b = (a, 2);  ").unwrap();
+    assert!(outcome.value.is_none());
+    assert_eq!(outcome.defined, ["a", "b"]);
+    assert_eq!(value(&mut session, "second(b)"), "2");
+
+    // Comments after the `;` are not an expression.
+    for source in [
+        "let c = 3; // three",
+        "let c = 3; /* three */\n",
+        "let c = 3; /* ; */",
+    ] {
+        let outcome = session.eval(source).unwrap();
+        assert!(outcome.value.is_none(), "{source}");
+        assert_eq!(outcome.defined, ["c"]);
+    }
+    let outcome = session.eval("c // ;").unwrap();
+    assert_eq!(outcome.value.expect("no value").to_string(), "3");
+}
+
+#[test]
+fn recursive_closure_test() {
+    let mut session = Session::new();
+    session
+        .eval("let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };")
+        .unwrap();
+    session.eval("let fib10 = fn () => { fib(10) };").unwrap();
+    assert_eq!(value(&mut session, "fib(15)"), "610");
+    // Redefining a name does not change what earlier closures refer to.
+    session.eval("let fib = fn (n) => { 0 };").unwrap();
+    assert_eq!(value(&mut session, "fib(15)"), "0");
+    assert_eq!(value(&mut session, "fib10()"), "55");
+}
+
+#[test]
+fn mutual_recursion_test() {
+    let mut session = Session::new();
+    let outcome = session
+        .eval(
+            "let even = fn (n) => { if (n == 0) { true } else { odd(n - 1) } }; \
+             let odd = fn (n) => { if (n == 0) { false } else { even(n - 1) } };",
+        )
+        .unwrap();
+    assert_eq!(outcome.defined, ["even", "odd"]);
+    assert_eq!(value(&mut session, "(even(4), odd(4))"), "(true, false)");
+    assert_eq!(
+        session.type_of("even").unwrap().to_string(),
+        "(int) -> bool"
+    );
+}
+
+#[test]
+fn errors_keep_session_test() {
+    let mut session = Session::new();
+    session.eval("let x = 1;").unwrap();
+    assert!(matches!(session.eval("x +"), Err(SessionError::Parse(_))));
+    assert!(matches!(
+        session.eval("let y = 2; y + z"),
+        Err(SessionError::Runtime(_))
+    ));
+    assert_eq!(session.names(), ["x"]);
+    assert_eq!(value(&mut session, "x"), "1");
+}
+
+#[test]
+fn print_has_no_value_test() {
+    let mut session = Session::new();
+    let outcome = session.eval("let x = 1; print(x)").unwrap();
+    assert!(outcome.value.is_none());
+    assert_eq!(outcome.defined, ["x"]);
+}
+
+#[test]
+fn type_of_test() {
+    let mut session = Session::new();
+    session.eval("let id = fn (x) => { x };").unwrap();
+    session
+        .eval("let bad = fn (x) => { x + true * 2 };")
+        .unwrap();
+    session.eval("let pair = (id(1), id(\"a\"));").unwrap();
+    assert_eq!(session.type_of("id").unwrap().to_string(), "('a) -> 'a");
+    assert_eq!(session.type_of("pair").unwrap().to_string(), "(int, str)");
+    // Definitions that do not type check are left out.
+    assert!(matches!(session.type_of("bad"), Err(SessionError::Type(_))));
+    assert!(matches!(
+        session.type_of("id(true) + 1"),
+        Err(SessionError::Type(_))
+    ));
+}
+
+#[test]
+fn load_test() {
+    let path = std::env::temp_dir().join("rinha_session_load_test.rinha");
+    std::fs::write(&path, "let three = 3; let add = fn (a, b) => { a + b }; 0").unwrap();
+    let mut session = Session::new();
+    let outcome = session.load(&path).unwrap();
+    assert_eq!(outcome.defined, ["three", "add"]);
+    assert_eq!(value(&mut session, "add(three, 1)"), "4");
+
+    std::fs::write(&path, "let three = 33; 0").unwrap();
+    session.reload().expect("nothing loaded").unwrap();
+    assert_eq!(value(&mut session, "add(three, 1)"), "34");
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+        session.load(&path),
+        Err(SessionError::Read { .. })
+    ));
+}
+
+#[test]
+fn load_json_test() {
+    let path = std::env::temp_dir().join("rinha_session_load_test.json");
+    let source = "let half = fn (n) => { n / 2 }; 0";
+    let file = File::from(rinha::parser::parse_or_report("half.rinha", source).unwrap());
+    std::fs::write(&path, file.to_json(false).unwrap()).unwrap();
+    let mut session = Session::new();
+    session.load(&path).unwrap();
+    assert_eq!(session.loaded(), Some(path.as_path()));
+    assert_eq!(value(&mut session, "half(10)"), "5");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reports_show_defining_input_test() {
+    let mut session = Session::new();
+    session.eval("let f = fn (x) => { x + 1 * true };").unwrap();
+    let error = session.eval("f(1)").unwrap_err();
+    let reports = session.reports(error);
+    let rendered = format!("{:?}", reports[0]);
+    assert!(rendered.contains("<repl:1>"), "{rendered}");
+}
+
+#[test]
+fn interrupt_keeps_session_test() {
+    let mut session = Session::new();
+    session.eval("let x = 1;").unwrap();
+    let interrupt = session.interrupt();
+    interrupt.interrupt();
+    let error = session
+        .eval("let f = fn () => { f() }; let y = 2; f()")
+        .unwrap_err();
+    assert!(
+        matches!(
+            error,
+            SessionError::Runtime(RuntimeError::Interrupted { .. })
+        ),
+        "{error:?}"
+    );
+    interrupt.reset();
+    assert_eq!(session.names(), ["x"]);
+    assert_eq!(value(&mut session, "x"), "1");
+}